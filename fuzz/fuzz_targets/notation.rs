@@ -0,0 +1,8 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate qwirkler;
+
+fuzz_target!(|data: &[u8]| {
+    qwirkler::fuzz::notation(data);
+});
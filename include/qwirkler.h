@@ -0,0 +1,53 @@
+/* C API for the qwirkler engine.  Mirrors src/ffi.rs, which documents each function.
+ *
+ * Link against libqwirkler.so (built by `cargo build --release` into target/release).
+ * Every char * returned here belongs to the caller and must be released with
+ * qwirkler_string_free.  Moves use the notation `0,0 R 11 12` or `swap`.
+ */
+#ifndef QWIRKLER_H
+#define QWIRKLER_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct QwirklerGame QwirklerGame;
+
+#define QWIRKLER_OK 0
+#define QWIRKLER_BAD_ARGUMENT -1
+#define QWIRKLER_BAD_NOTATION -2
+#define QWIRKLER_ILLEGAL_MOVE -3
+/* The engine panicked.  Pointer results are NULL instead, and the game should only be freed. */
+#define QWIRKLER_INTERNAL_ERROR -4
+
+/* rules may be NULL for the standard rules.  Returns NULL on bad rules or too many players. */
+QwirklerGame *qwirkler_game_new(size_t players, const char *rules, uint64_t seed);
+void qwirkler_game_free(QwirklerGame *game);
+void qwirkler_string_free(char *s);
+
+/* Both return -1 if game is NULL. */
+int qwirkler_game_turn(const QwirklerGame *game);
+int qwirkler_game_is_over(const QwirklerGame *game);
+
+/* NULL if player is out of range. */
+char *qwirkler_game_state_json(const QwirklerGame *game, size_t player);
+char *qwirkler_game_position(const QwirklerGame *game, size_t player);
+/* A JSON array of {"move": "...", "score": n, "breakdown": [...]} for the player whose turn
+   it is, the breakdown listing each line scored (see ffi.rs). */
+char *qwirkler_game_legal_moves(const QwirklerGame *game);
+
+/* Returns QWIRKLER_OK or a negative status; see qwirkler_game_last_error. */
+int qwirkler_game_apply_move(QwirklerGame *game, const char *move);
+/* strategy is "greedy" or "search".  NULL once the game is over. */
+char *qwirkler_game_best_move(QwirklerGame *game, const char *strategy, uint32_t movetime_ms);
+/* An empty string if nothing has failed yet. */
+char *qwirkler_game_last_error(const QwirklerGame *game);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -1,5 +1,8 @@
 extern crate test;
 use gamestate::GameState;
+use linevalidator::LineValidator;
+use piece;
+use rules::Rules;
 
 #[bench]
 fn entire_game(b: &mut test::Bencher) {
@@ -16,3 +19,37 @@ fn entire_game(b: &mut test::Bencher) {
         println!("total = {}", game_state.total_score());
     })
 }
+
+/// The same game every iteration, so that runs can be compared.
+#[bench]
+fn entire_seeded_game(b: &mut test::Bencher) {
+    b.iter(|| {
+        let mut game_state = GameState::with_rules(2, Rules::standard(), 1);
+        while let Some(chosen_move) = game_state.generate_best_move() {
+            game_state.apply_move(&chosen_move);
+        }
+        game_state.total_score()
+    })
+}
+
+/// Tries to extend a line with every piece in the bag, one pair of pieces at a time.
+#[bench]
+fn line_validation(b: &mut test::Bencher) {
+    let bag = piece::make_bag_with_copies(1);
+    b.iter(|| {
+        let mut valid = 0;
+        for &first in &bag {
+            let lv = LineValidator::new(first);
+            for &second in &bag {
+                if let Some(lv2) = lv.clone_extend(second) {
+                    for &third in &bag {
+                        if lv2.clone_extend(third).is_some() {
+                            valid += 1;
+                        }
+                    }
+                }
+            }
+        }
+        valid
+    })
+}
@@ -1,10 +1,12 @@
-use piece::Piece;
-use linevalidator::LineValidator;
+use piece::{Colour, Piece, Shape};
+use linevalidator::{self, LineValidator};
 use direction::{Square, Direction};
-use std::{fmt, string};
+use std::{cmp, fmt, string};
 use player::Score;
 use partial::Partial;
-use std::collections::HashSet;
+use mv::{IllegalMove, ScoreBreakdown};
+use std::collections::{HashMap, HashSet};
+use zobrist;
 
 
 /// The representation of an arrangement of Qwirkle pieces.
@@ -19,15 +21,21 @@ use std::collections::HashSet;
 /// # Invariants
 ///
 /// * The `perimeter` hashset stores free locations that are adjacent to an occupied square
-/// * The `min_x`, `max_x` etc variables define a bounding box for the whole game's arrangement
-/// of pieces. (Coordinates are inclusive)
+/// * `cross_checks` has an entry for every perimeter square, describing the lines through it
+/// * The `min_x`, `max_x` etc variables define the smallest bounding box around the whole
+/// game's arrangement of pieces (or just 0,0 while there are none). (Coordinates are inclusive)
+/// * `zobrist` is the XOR of `zobrist::square_key` for every piece on the board
+#[derive(Clone)]
 pub struct Board {
     board: [[Option<Piece>; DIM_2]; DIM_2],
     perimeter: HashSet<Square>,
+    /// For each perimeter square, what its horizontal and vertical lines allow (in that order).
+    cross_checks: HashMap<Square, [CrossCheck; 2]>,
     min_x: isize,
     max_x: isize,
     min_y: isize,
     max_y: isize,
+    zobrist: u64,
 }
 
 /// This constant determines the size of array we allocate for one `Board`.  Theoretically, you could
@@ -35,34 +43,167 @@ pub struct Board {
 const DIM: isize = 25;
 const DIM_2: usize = (2 * DIM) as usize;
 
+/// What the line through an empty square allows there, like the cross-checks of a Scrabble move
+/// generator: placing a piece along one direction must also fit the perpendicular line.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct CrossCheck {
+    /// Bit `piece.index()` is set for every piece the line allows on the square.
+    pub allowed: u64,
+    /// The points the line would score, including any Qwirkle bonus, or 0 if there is no line.
+    pub score: Score,
+}
+
+/// What every piece in a line has in common.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum LineAttribute {
+    Colour(Colour),
+    Shape(Shape),
+}
+
+impl LineAttribute {
+    /// The colour or shape all of `pieces` share, if they share one.  (Two different pieces
+    /// can't share both.)
+    pub fn shared_by(pieces: &[Piece]) -> Option<LineAttribute> {
+        let first = match pieces.first() {
+            None => return None,
+            Some(piece) => piece,
+        };
+        if pieces.iter().all(|p| p.colour == first.colour) {
+            return Some(LineAttribute::Colour(first.colour));
+        }
+        if pieces.iter().all(|p| p.shape == first.shape) {
+            return Some(LineAttribute::Shape(first.shape));
+        }
+        return None;
+    }
+}
+
+/// A run of two or more pieces that can't be made any longer without placing more: the
+/// squares at both ends are empty.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Line {
+    /// The leftmost or lowest piece's square.
+    pub start: Square,
+    /// `R` for a row and `U` for a column.
+    pub direction: Direction,
+    /// The pieces, from `start` onwards.
+    pub pieces: Vec<Piece>,
+    /// `None` only for a line that breaks the rules, which `put()` doesn't check for.
+    pub attribute: Option<LineAttribute>,
+}
+
+impl Line {
+    pub fn squares(&self) -> Vec<Square> {
+        return self.direction.apply_all(self.start, self.pieces.len());
+    }
+}
+
+/// The cross-check of a square with no neighbours along the line.
+const ANYTHING: CrossCheck = CrossCheck {
+    allowed: (1 << 36) - 1,
+    score: 0,
+};
+
+/// Which entry of `cross_checks` describes the perpendicular line for moves along `direction`.
+fn perpendicular_axis(direction: &Direction) -> usize {
+    match *direction {
+        Direction::U | Direction::D => 0,
+        Direction::L | Direction::R => 1,
+    }
+}
+
+/// Which copies of a board `Board::canonical` treats as the same position.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Symmetry {
+    /// Boards that are shifted copies of each other.
+    Translation,
+    /// Boards that are shifted, rotated or mirrored copies of each other.
+    All,
+}
+
+/// A board's pieces as `Board::canonical` lists them.  Copies of a position give equal
+/// values with equal hashes, so this can key a position database.  (`Board` has no `==` of
+/// its own: compare `zobrist` or `tiles` to check for exactly the same squares.)
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct CanonicalBoard(pub Vec<(Square, Piece)>);
+
+/// The `i`th of the eight rotations and reflections of the plane (0 leaves it alone).
+fn transform(i: usize, (x, y): Square) -> Square {
+    match i {
+        0 => (x, y),
+        1 => (-x, y),
+        2 => (x, -y),
+        3 => (-x, -y),
+        4 => (y, x),
+        5 => (-y, x),
+        6 => (y, -x),
+        _ => (-y, -x),
+    }
+}
+
+/// Moves `tiles` so that their bounding box starts at `0,0`, and lists them row by row.
+fn normalise(mut tiles: Vec<(Square, Piece)>) -> Vec<(Square, Piece)> {
+    let min_x = tiles.iter().map(|&((x, _), _)| x).min().unwrap_or(0);
+    let min_y = tiles.iter().map(|&((_, y), _)| y).min().unwrap_or(0);
+    for tile in tiles.iter_mut() {
+        tile.0 = ((tile.0).0 - min_x, (tile.0).1 - min_y);
+    }
+    tiles.sort_by_key(|&((x, y), _)| (y, x));
+    return tiles;
+}
+
 impl Board {
     pub fn new() -> Board {
         let new_board = [[None; DIM_2]; DIM_2];
         Board {
             board: new_board,
             perimeter: HashSet::new(),
+            cross_checks: HashMap::new(),
             min_x: 0,
             max_x: 0,
             min_y: 0,
             max_y: 0,
+            zobrist: 0,
         }
     }
 
-    pub fn get_start_squares(&self) -> Vec<(Square, Direction)> {
-        let mut result: Vec<(Square, Direction)> = Vec::new();
+    /// A hash of the pieces on the board and where they are, kept up to date by `put` and
+    /// `undo`.  Boards with the same pieces on the same squares have the same hash, however
+    /// they were built.
+    pub fn zobrist(&self) -> u64 {
+        return self.zobrist;
+    }
 
-        for &direction in &Direction::all() {
-            for &sq in &self.perimeter {
-                if self.get(direction.apply(sq)).is_none() {
-                    result.push((sq, direction));
+    /// Where to look for placements: every empty square that a line of pieces could start on,
+    /// with the direction it runs in and how many pieces it needs to reach the perimeter (and so
+    /// connect to the existing tiles).
+    ///
+    /// Lines only run right or up, from their lowest square, so each placement has exactly one
+    /// start.  On an empty board, where the first move has to cover `0,0`, lines are measured
+    /// to `0,0` instead of to the perimeter.
+    pub fn start_squares(&self) -> Vec<(Square, Direction, usize)> {
+        let targets: Vec<Square> = if self.perimeter.is_empty() {
+            vec![(0, 0)]
+        } else {
+            self.perimeter.iter().cloned().collect()
+        };
+        let mut starts = vec![];
+        for &sq in &targets {
+            for &direction in &[Direction::R, Direction::U] {
+                let mut start = sq;
+                for length in 1..7 {
+                    if !self.in_bounds(start) || self.get(start).is_some() {
+                        break;
+                    }
+                    starts.push((start, direction, length));
+                    start = direction.opposite().apply(start);
                 }
             }
         }
-        if result.len() == 0 {
-            return vec![((0, 0), Direction::initial())];
-        } else {
-            return result;
-        }
+        // a start can reach several perimeter squares, the nearest one is what matters
+        starts.sort_by_key(|&((x, y), direction, length)| (direction == Direction::U, y, x, length));
+        starts.dedup_by_key(|&mut (sq, direction, _)| (sq, direction));
+        return starts;
     }
 
     /// allows() returns `true` iff the supplied `partial` play is legal for this board.
@@ -78,17 +219,16 @@ impl Board {
 
         // since the prefix of this line was already passed validation,
         // we just need to check the last perpendicular.
-        let last_piece = partial.pieces[partial.pieces.len() - 1];
-        let new_perp_score = match self.check_perpendicular(partial.last_square,
-                                                            &partial.direction,
-                                                            last_piece) {
-            None => return false,
-            Some(v) => v,
-        };
+        let last_piece = partial.last_piece();
+        let check = self.cross_check(partial.last_square, &partial.direction);
+        if check.allowed & (1 << last_piece.index()) == 0 {
+            return false;
+        }
+        let new_perp_score = check.score;
 
         // if we have no line validator, we must construct one and save it
         if partial.main_validator.is_none() {
-            let mut first_lv = LineValidator::new(partial.pieces[0]);
+            let mut first_lv = LineValidator::new(partial.pieces()[0]);
             // TODO: maybe re-express this whole pattern as a fold?
             if !first_lv.extend_from_iter(&mut self.non_blank_iter(partial.start_square,
                                                                    partial.direction.opposite())) {
@@ -98,48 +238,59 @@ impl Board {
         }
 
         if let Some(ref mut lv) = partial.main_validator.as_mut() {
-            if !self.get(partial.direction.apply(partial.last_square)).is_none() {
+            let runs_into_tiles = self.get(partial.direction.apply(partial.last_square)).is_some();
+            if runs_into_tiles {
                 // the line doesn't end in a blank, we have to continue validating in that direction
                 if !lv.extend_from_iter(&mut self.non_blank_iter(partial.last_square,
                                                                  partial.direction)) {
                     return false;
                 }
-                lv.seal() // this also implies that this partial can't be extended
             }
-            partial.mainline_score = lv.length +
-                                     if lv.length == 6 {
-                6
-            } else {
-                0
+            // a single piece only scores for its own line if it has no other line to score for
+            // (ie it's the first move)
+            partial.mainline_score = match lv.length {
+                1 if partial.perp_scores + new_perp_score > 0 => 0,
+                6 => 12,
+                length => length,
             };
+            if runs_into_tiles {
+                lv.seal() // this also implies that this partial can't be extended
+            }
         }
 
         partial.perp_scores += new_perp_score;
+        partial.perp_line_scores[partial.pieces().len() - 1] = new_perp_score;
         return true;
     }
 
-    // returns the score won from a perpendicular line or return None if it's invalid.
-    fn check_perpendicular(&self,
-                           square: Square,
-                           direction: &Direction,
-                           piece: Piece)
-                           -> Option<Score> {
-        let mut perp_lv = LineValidator::new(piece);
+    /// What the line perpendicular to `direction` through the empty square `sq` allows there.
+    /// This is a single lookup: the answers are kept up to date by `put()`.
+    pub fn cross_check(&self, sq: Square, direction: &Direction) -> CrossCheck {
+        match self.cross_checks.get(&sq) {
+            None => ANYTHING,
+            Some(checks) => checks[perpendicular_axis(direction)],
+        }
+    }
+
+    /// Works out `cross_check()` from scratch from the pieces either side of `sq`.
+    fn compute_cross_check(&self, sq: Square, direction: &Direction) -> CrossCheck {
         let (d1, d2) = direction.perpendiculars();
-        if !perp_lv.extend_from_iter(&mut self.non_blank_iter(square, d1)
-                                              .chain(self.non_blank_iter(square, d2))) {
-            return None;
-        }
-        if perp_lv.length > 1 {
-            return Some(perp_lv.length +
-                        if perp_lv.length == 6 {
-                6
-            } else {
-                0
-            });
-        } else {
-            return Some(0); // ensures we don't double count each piece!
+        let length = 1 + self.non_blank_iter(sq, d1).count() + self.non_blank_iter(sq, d2).count();
+        if length == 1 {
+            return ANYTHING;
         }
+        // the order of a line doesn't matter, so the pieces either side can be checked first
+        let mut lv = LineValidator::empty();
+        let allowed = if lv.extend_from_iter(&mut self.non_blank_iter(sq, d1)
+                                                      .chain(self.non_blank_iter(sq, d2))) {
+            lv.allowed()
+        } else {
+            0
+        };
+        return CrossCheck {
+            allowed: allowed,
+            score: length + if length == 6 { 6 } else { 0 },
+        };
     }
 
     fn non_blank_iter(&self, start: Square, direction: Direction) -> NonBlankIterator {
@@ -154,10 +305,13 @@ impl Board {
     /// Preconditions:
     /// The start_sq, direction and pieces must describe a legal move according to the rules of qwirkle.
     pub fn put(&mut self, start_sq: Square, direction: &Direction, pieces: &Vec<Piece>) {
+        let was_empty = self.perimeter.is_empty();
+
         // compute the new array
         let squares = direction.apply_all(start_sq, pieces.len());
         for (&(x, y), &piece) in squares.iter().zip(pieces.iter()) {
             self.board[(x + DIM) as usize][(y + DIM) as usize] = Some(piece);
+            self.zobrist ^= zobrist::square_key((x, y), piece);
         }
 
         // compute the new perimeter
@@ -182,15 +336,313 @@ impl Board {
             }
         }
 
+        // the only cross-checks that change are those of the empty squares at either end of
+        // every line running through the new pieces
+        for sq in &squares {
+            self.cross_checks.remove(sq);
+        }
+        self.update_line_ends(&squares);
+
         // update the bounding box.
+        if was_empty {
+            self.reset_bounding_box(start_sq);
+        }
         self.stretch_bounding_box(squares[0]);
         self.stretch_bounding_box(squares[pieces.len() - 1]);
     }
 
+    /// Takes back the `count` pieces that the last `put` placed from `start_sq` along
+    /// `direction`, leaving the board (and its hash) as it was before.
+    pub fn undo(&mut self, start_sq: Square, direction: &Direction, count: usize) {
+        let squares = direction.apply_all(start_sq, count);
+        for &(x, y) in &squares {
+            if let Some(piece) = self.get((x, y)) {
+                self.zobrist ^= zobrist::square_key((x, y), piece);
+            }
+            self.board[(x + DIM) as usize][(y + DIM) as usize] = None;
+        }
+
+        // the emptied squares and their neighbours are the only ones whose place on the
+        // perimeter can have changed
+        let mut affected = squares.clone();
+        for &sq in &squares {
+            for d in &Direction::all() {
+                affected.push(d.apply(sq));
+            }
+        }
+        for &sq in &affected {
+            let touches_tiles = Direction::all().iter().any(|d| self.get(d.apply(sq)).is_some());
+            if self.get(sq).is_none() && touches_tiles {
+                self.perimeter.insert(sq);
+            } else {
+                self.perimeter.remove(&sq);
+                self.cross_checks.remove(&sq);
+            }
+        }
+        for &sq in &squares {
+            if self.perimeter.contains(&sq) {
+                let checks = [self.compute_cross_check(sq, &Direction::U),
+                              self.compute_cross_check(sq, &Direction::L)];
+                self.cross_checks.insert(sq, checks);
+            }
+        }
+        self.update_line_ends(&squares);
+
+        // the bounding box can only shrink, so rebuild it from what is left
+        let tiles = self.tiles();
+        self.reset_bounding_box(tiles.first().map_or((0, 0), |&(sq, _)| sq));
+        for &(sq, _) in &tiles {
+            self.stretch_bounding_box(sq);
+        }
+    }
+
+    /// Recomputes the cross-checks of the empty squares at either end of every line through
+    /// `squares`.
+    fn update_line_ends(&mut self, squares: &[Square]) {
+        for &sq in squares {
+            for &d in &Direction::all() {
+                let mut end = d.apply(sq);
+                while self.get(end).is_some() {
+                    end = d.apply(end);
+                }
+                if self.perimeter.contains(&end) {
+                    let checks = [self.compute_cross_check(end, &Direction::U),
+                                  self.compute_cross_check(end, &Direction::L)];
+                    self.cross_checks.insert(end, checks);
+                }
+            }
+        }
+    }
+
+    /// The empty squares next to at least one piece.
+    pub fn perimeter(&self) -> &HashSet<Square> {
+        return &self.perimeter;
+    }
+
     pub fn get(&self, (x, y): Square) -> Option<Piece> {
         return self.board[(x + DIM) as usize][(y + DIM) as usize];
     }
 
+    /// Squares that a piece may be placed on.  The two outermost rings of the array are kept
+    /// blank, so that walking along any line from a piece or from a perimeter square always
+    /// stops before running off the edge.
+    pub fn in_bounds(&self, (x, y): Square) -> bool {
+        return x > -DIM + 1 && x < DIM - 2 && y > -DIM + 1 && y < DIM - 2;
+    }
+
+    /// Lists every piece on the board with its square, row by row.
+    pub fn tiles(&self) -> Vec<(Square, Piece)> {
+        return self.occupied().collect();
+    }
+
+    /// Goes through the occupied squares and their pieces, row by row, without collecting them.
+    pub fn occupied<'a>(&'a self) -> Occupied<'a> {
+        return Occupied {
+            board: self,
+            sq: (self.min_x, self.min_y),
+        };
+    }
+
+    /// Every maximal line of the board: each row and column of two or more pieces, listed by
+    /// their start squares, row by row, with a row before a column that starts on the same
+    /// square.
+    pub fn lines(&self) -> Vec<Line> {
+        let mut lines = vec![];
+        for (sq, piece) in self.occupied() {
+            for &direction in &[Direction::R, Direction::U] {
+                let starts_here = self.get(direction.opposite().apply(sq)).is_none() &&
+                                  self.get(direction.apply(sq)).is_some();
+                if starts_here {
+                    let mut pieces = vec![piece];
+                    pieces.extend(self.non_blank_iter(sq, direction));
+                    lines.push(Line {
+                        start: sq,
+                        direction: direction,
+                        attribute: LineAttribute::shared_by(&pieces),
+                        pieces: pieces,
+                    });
+                }
+            }
+        }
+        return lines;
+    }
+
+    /// The empty squares no piece can ever be placed on, because the row and the column
+    /// through them can't both take the same piece (or one of them is already complete).
+    /// Lines only grow, so a blocked square stays blocked for the rest of the game.  Listed row
+    /// by row.
+    pub fn blocked_squares(&self) -> Vec<Square> {
+        let mut blocked: Vec<Square> = self.perimeter
+                                           .iter()
+                                           .cloned()
+                                           .filter(|&sq| {
+                                               self.cross_check(sq, &Direction::R).allowed &
+                                               self.cross_check(sq, &Direction::U).allowed == 0
+                                           })
+                                           .collect();
+        blocked.sort_by_key(|&(x, y)| (y, x));
+        return blocked;
+    }
+
+    /// The board's pieces in a form that is the same for every copy of the position: shifted
+    /// so that the bounding box starts at `0,0` and, with `Symmetry::All`, rotated or mirrored
+    /// to whichever of the eight orientations lists its pieces first.  Tiles are listed row by
+    /// row, as `tiles` does.
+    pub fn canonical(&self, symmetry: Symmetry) -> CanonicalBoard {
+        let orientations = match symmetry {
+            Symmetry::Translation => 1,
+            Symmetry::All => 8,
+        };
+        let tiles = self.tiles();
+        let key = |tiles: &Vec<(Square, Piece)>| -> Vec<(isize, isize, usize)> {
+            tiles.iter().map(|&((x, y), piece)| (y, x, piece.index())).collect()
+        };
+        let first = (0..orientations)
+                        .map(|i| normalise(tiles.iter().map(|&(sq, piece)| (transform(i, sq), piece)).collect()))
+                        .min_by_key(|tiles| key(tiles))
+                        .unwrap();
+        return CanonicalBoard(first);
+    }
+
+    /// Returns the inclusive bounding box of all placed pieces as `(min, max)` squares.
+    pub fn bounding_box(&self) -> (Square, Square) {
+        return ((self.min_x, self.min_y), (self.max_x, self.max_y));
+    }
+
+    /// Checks the invariants above, and that every line on the board is valid, by working
+    /// everything out again from the pieces.  Returns a description of every problem found.
+    pub fn check_invariants(&self) -> Vec<String> {
+        let mut violations = vec![];
+        let mut tiles = vec![];
+        for x in -DIM..DIM {
+            for y in -DIM..DIM {
+                if let Some(piece) = self.get((x, y)) {
+                    tiles.push(((x, y), piece));
+                }
+            }
+        }
+
+        let mut zobrist = 0;
+        let mut perimeter = HashSet::new();
+        for &((x, y), piece) in &tiles {
+            zobrist ^= zobrist::square_key((x, y), piece);
+            if !self.in_bounds((x, y)) {
+                violations.push(format!("{},{} is off the board", x, y));
+            }
+            for &direction in &[Direction::R, Direction::U] {
+                let mut line = vec![];
+                let mut sq = (x, y);
+                while self.get(direction.opposite().apply(sq)).is_some() {
+                    sq = direction.opposite().apply(sq);
+                }
+                while let Some(piece) = self.get(sq) {
+                    line.push(piece);
+                    sq = direction.apply(sq);
+                }
+                if !linevalidator::is_valid_line(&line) {
+                    violations.push(format!("the line through {},{} isn't valid", x, y));
+                }
+            }
+            for d in &Direction::all() {
+                if self.get(d.apply((x, y))).is_none() {
+                    perimeter.insert(d.apply((x, y)));
+                }
+            }
+        }
+
+        if zobrist != self.zobrist {
+            violations.push("the hash doesn't match the pieces".to_string());
+        }
+        if perimeter != self.perimeter {
+            violations.push(format!("the perimeter should be {} squares, not {}",
+                                    perimeter.len(),
+                                    self.perimeter.len()));
+        }
+        for &sq in &self.perimeter {
+            for direction in &[Direction::U, Direction::L] {
+                if self.cross_checks.get(&sq).is_none() ||
+                   self.cross_check(sq, direction) != self.compute_cross_check(sq, direction) {
+                    violations.push(format!("the cross-check at {},{} is out of date", sq.0, sq.1));
+                }
+            }
+        }
+        if self.cross_checks.len() != self.perimeter.len() {
+            violations.push("there are cross-checks for squares off the perimeter".to_string());
+        }
+
+        let mut expected = tiles.first().map_or(((0, 0), (0, 0)), |&(sq, _)| (sq, sq));
+        for &((x, y), _) in &tiles {
+            let ((min_x, min_y), (max_x, max_y)) = expected;
+            expected = ((cmp::min(x, min_x), cmp::min(y, min_y)), (cmp::max(x, max_x), cmp::max(y, max_y)));
+        }
+        if expected != self.bounding_box() {
+            violations.push(format!("the bounding box should be {:?}, not {:?}",
+                                    expected,
+                                    self.bounding_box()));
+        }
+        return violations;
+    }
+
+    /// Validates an arbitrary placement (eg one typed in by a human) and returns how it scores.
+    ///
+    /// Unlike `allows()`, this makes no assumptions about the move: every square is checked
+    /// for bounds and occupancy, and the pieces must connect to the existing arrangement.
+    pub fn score_placement(&self,
+                           start_sq: Square,
+                           direction: &Direction,
+                           pieces: &Vec<Piece>)
+                           -> Result<ScoreBreakdown, IllegalMove> {
+        if pieces.len() == 0 {
+            return Err(IllegalMove::NoPieces);
+        }
+        if pieces.len() > 6 {
+            return Err(IllegalMove::TooManyPieces);
+        }
+        // checked first, as stepping along from a start near the ends of isize would overflow
+        if !self.in_bounds(start_sq) {
+            return Err(IllegalMove::OutOfBounds(start_sq));
+        }
+        let squares = direction.apply_all(start_sq, pieces.len());
+        for &sq in &squares {
+            if !self.in_bounds(sq) {
+                return Err(IllegalMove::OutOfBounds(sq));
+            }
+            if self.get(sq).is_some() {
+                return Err(IllegalMove::Occupied(sq));
+            }
+        }
+        let connected = if self.perimeter.is_empty() {
+            squares.contains(&(0, 0))
+        } else {
+            squares.iter().any(|sq| self.perimeter.contains(sq))
+        };
+        if !connected {
+            return Err(IllegalMove::NotConnected);
+        }
+
+        let mut partial = Partial::new(start_sq, direction, pieces[0]);
+        if !self.allows(&mut partial) {
+            return Err(IllegalMove::InvalidLine(start_sq, pieces[0]));
+        }
+        for i in 1..pieces.len() {
+            partial = match partial.try_extend(pieces[i]) {
+                None => return Err(IllegalMove::InvalidLine(squares[i], pieces[i])),
+                Some(extended) => extended,
+            };
+            if !self.allows(&mut partial) {
+                return Err(IllegalMove::InvalidLine(squares[i], pieces[i]));
+            }
+        }
+        return Ok(partial.breakdown());
+    }
+
+    fn reset_bounding_box(&mut self, (x, y): Square) {
+        self.min_x = x;
+        self.max_x = x;
+        self.min_y = y;
+        self.max_y = y;
+    }
+
     fn stretch_bounding_box(&mut self, (x, y): Square) {
         if x < self.min_x {
             self.min_x = x;
@@ -246,3 +698,199 @@ impl<'a> Iterator for NonBlankIterator<'a> {
         return self.board.get(self.sq);
     }
 }
+
+/// The occupied squares of a board, from `Board::occupied`.
+pub struct Occupied<'a> {
+    board: &'a Board,
+    /// The next square to look at.
+    sq: Square,
+}
+
+impl<'a> Iterator for Occupied<'a> {
+    type Item = (Square, Piece);
+
+    fn next(&mut self) -> Option<(Square, Piece)> {
+        let ((min_x, _), (max_x, max_y)) = self.board.bounding_box();
+        while self.sq.1 <= max_y {
+            let sq = self.sq;
+            self.sq = if sq.0 < max_x { (sq.0 + 1, sq.1) } else { (min_x, sq.1 + 1) };
+            if let Some(piece) = self.board.get(sq) {
+                return Some((sq, piece));
+            }
+        }
+        return None;
+    }
+}
+
+#[test]
+fn score_placement_should_require_the_origin_on_an_empty_board() {
+    let board = Board::new();
+    let pieces = vec![Piece::new(Colour::R, Shape::A)];
+    assert!(board.score_placement((1, 1), &Direction::R, &pieces) ==
+            Err(IllegalMove::NotConnected));
+    assert!(board.score_placement((0, 0), &Direction::R, &pieces).map(|b| b.total()) == Ok(1));
+}
+
+#[test]
+fn score_placement_should_score_lines_through_existing_pieces() {
+    let mut board = Board::new();
+    board.put((0, 0),
+              &Direction::R,
+              &vec![Piece::new(Colour::R, Shape::A), Piece::new(Colour::R, Shape::B)]);
+    let pieces = vec![Piece::new(Colour::R, Shape::C)];
+    assert!(board.score_placement((2, 0), &Direction::R, &pieces).map(|b| b.total()) == Ok(3));
+    assert!(board.score_placement((-1, 0), &Direction::L, &pieces).map(|b| b.total()) == Ok(3));
+}
+
+#[test]
+fn score_placement_should_reject_bad_lines() {
+    let mut board = Board::new();
+    board.put((0, 0), &Direction::R, &vec![Piece::new(Colour::R, Shape::A)]);
+    let duplicate = vec![Piece::new(Colour::R, Shape::A)];
+    assert!(board.score_placement((1, 0), &Direction::R, &duplicate) ==
+            Err(IllegalMove::InvalidLine((1, 0), duplicate[0])));
+    assert!(board.score_placement((0, 0), &Direction::R, &duplicate) ==
+            Err(IllegalMove::Occupied((0, 0))));
+}
+
+#[test]
+fn score_placement_should_reject_huge_coordinates_without_overflowing() {
+    let board = Board::new();
+    let pieces = vec![Piece::new(Colour::R, Shape::A), Piece::new(Colour::R, Shape::B)];
+    for &(sq, direction) in &[((isize::MAX, 0), Direction::R),
+                              ((isize::MIN, 0), Direction::L),
+                              ((0, isize::MAX), Direction::U),
+                              ((0, isize::MIN), Direction::D)] {
+        assert!(board.score_placement(sq, &direction, &pieces) == Err(IllegalMove::OutOfBounds(sq)));
+    }
+}
+
+#[test]
+fn start_squares_should_cover_the_origin_on_an_empty_board() {
+    let starts = Board::new().start_squares();
+    // lines of one to six pieces through 0,0, running right or up
+    assert!(starts.len() == 12);
+    assert!(starts.contains(&((-5, 0), Direction::R, 6)) && starts.contains(&((0, -1), Direction::U, 2)));
+    assert!(!starts.contains(&((-6, 0), Direction::R, 7)));
+}
+
+#[test]
+fn cross_checks_should_match_a_recomputation_after_every_move() {
+    use gamestate::GameState;
+    use rules::Rules;
+    let mut game_state = GameState::with_rules(2, Rules::standard(), 17);
+    for _ in 0..20 {
+        let chosen_move = game_state.generate_best_move().unwrap();
+        game_state.apply_move(&chosen_move);
+        let board = &game_state.board;
+        let keys: HashSet<Square> = board.cross_checks.keys().cloned().collect();
+        assert!(keys == board.perimeter);
+        for &sq in &board.perimeter {
+            for direction in &Direction::all() {
+                assert!(board.cross_check(sq, direction) == board.compute_cross_check(sq, direction));
+            }
+        }
+    }
+}
+
+#[test]
+fn zobrist_should_not_depend_on_move_order() {
+    let a = Piece::new(Colour::R, Shape::A);
+    let b = Piece::new(Colour::R, Shape::B);
+    let c = Piece::new(Colour::O, Shape::A);
+    let d = Piece::new(Colour::Y, Shape::A);
+
+    let mut board1 = Board::new();
+    board1.put((0, 0), &Direction::R, &vec![a, b]);
+    board1.put((0, 1), &Direction::U, &vec![c, d]);
+
+    let mut board2 = Board::new();
+    board2.put((0, 2), &Direction::D, &vec![d, c]);
+    board2.put((0, 0), &Direction::U, &vec![a]);
+    board2.put((1, 0), &Direction::R, &vec![b]);
+
+    assert!(board1.zobrist() == board2.zobrist());
+    assert!(board1.zobrist() != 0);
+    board2.undo((1, 0), &Direction::R, 1);
+    assert!(board1.zobrist() != board2.zobrist());
+    assert!(Board::new().zobrist() == 0);
+}
+
+#[test]
+fn undo_should_restore_the_board() {
+    use gamestate::GameState;
+    use mv::Move;
+    use rules::Rules;
+    let mut game_state = GameState::with_rules(2, Rules::standard(), 5);
+    while !game_state.is_over() {
+        let chosen_move = game_state.generate_best_move().unwrap_or(Move::SwapPieces);
+        if let Move::PlacePieces(sq, ref direction, ref pieces, _) = chosen_move {
+            let mut board = game_state.board.clone();
+            board.put(sq, direction, pieces);
+            board.undo(sq, direction, pieces.len());
+            let before = &game_state.board;
+            assert!(board.zobrist() == before.zobrist());
+            assert!(board.tiles() == before.tiles());
+            assert!(board.perimeter == before.perimeter);
+            assert!(board.cross_checks == before.cross_checks);
+            assert!(board.bounding_box() == before.bounding_box());
+        }
+        game_state.apply_move(&chosen_move);
+    }
+}
+
+#[test]
+fn canonical_should_ignore_where_the_board_is_and_which_way_round() {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+    let a = Piece::new(Colour::R, Shape::A);
+    let b = Piece::new(Colour::R, Shape::B);
+    let c = Piece::new(Colour::O, Shape::A);
+    let build = |placements: &[(Square, Direction, Vec<Piece>)]| {
+        let mut board = Board::new();
+        for &(sq, direction, ref pieces) in placements {
+            board.put(sq, &direction, pieces);
+        }
+        board
+    };
+    let hash = |board: &Board| {
+        let mut hasher = DefaultHasher::new();
+        board.canonical(Symmetry::All).hash(&mut hasher);
+        hasher.finish()
+    };
+
+    let here = build(&[((0, 0), Direction::R, vec![a, b]), ((0, 1), Direction::U, vec![c])]);
+    let shifted = build(&[((5, -3), Direction::R, vec![a, b]), ((5, -2), Direction::U, vec![c])]);
+    let rotated = build(&[((0, 0), Direction::D, vec![a, b]), ((1, 0), Direction::R, vec![c])]);
+    let different = build(&[((0, 0), Direction::R, vec![a, b]), ((1, 1), Direction::U, vec![c])]);
+
+    assert!(here.canonical(Symmetry::Translation) == shifted.canonical(Symmetry::Translation));
+    assert!(here.canonical(Symmetry::Translation) != rotated.canonical(Symmetry::Translation));
+    assert!(here.canonical(Symmetry::Translation).0[0] == ((0, 0), a));
+    assert!(here.canonical(Symmetry::All) == shifted.canonical(Symmetry::All) && hash(&here) == hash(&shifted));
+    assert!(here.canonical(Symmetry::All) == rotated.canonical(Symmetry::All) && hash(&here) == hash(&rotated));
+    assert!(here.canonical(Symmetry::All) != different.canonical(Symmetry::All));
+    assert!(Board::new().canonical(Symmetry::All) == CanonicalBoard(vec![]));
+}
+
+#[test]
+fn lines_and_blocked_squares_should_describe_the_board() {
+    let mut board = Board::new();
+    let piece = |colour, shape| Piece::new(colour, shape);
+    board.put((0, 0), &Direction::R, &vec![piece(Colour::R, Shape::A), piece(Colour::R, Shape::B)]);
+    board.put((2, 1), &Direction::U, &vec![piece(Colour::O, Shape::A), piece(Colour::Y, Shape::A)]);
+
+    let lines = board.lines();
+    assert!(lines.len() == 2);
+    assert!(lines[0].start == (0, 0) && lines[0].direction == Direction::R);
+    assert!(lines[0].attribute == Some(LineAttribute::Colour(Colour::R)));
+    assert!(lines[1].squares() == vec![(2, 1), (2, 2)]);
+    assert!(lines[1].attribute == Some(LineAttribute::Shape(Shape::A)));
+
+    // 2,0 ends a red row and a column of circles, and the red circle is already in the row
+    assert!(board.blocked_squares() == vec![(2, 0)]);
+
+    let occupied: Vec<Square> = board.occupied().map(|(sq, _)| sq).collect();
+    assert!(occupied == vec![(0, 0), (1, 0), (2, 1), (2, 2)]);
+    assert!(Board::new().occupied().next().is_none() && Board::new().lines().is_empty());
+}
@@ -0,0 +1,617 @@
+use gamestate::{self, GameState, Hint};
+use interactive::{self, Seat};
+use json::Json;
+use lobby::{self, Lobby};
+use mv::Move;
+use notation;
+use render::{self, Overlay, RenderMode};
+use rand::{thread_rng, Rng};
+use engine;
+use rules::Rules;
+use server;
+use strategy::{self, Strategy};
+use svg;
+use std::fs;
+use std::io::{self, Write};
+use std::net::{IpAddr, Ipv4Addr, TcpListener};
+use std::path::Path;
+
+pub const USAGE: &'static str = "\
+Usage: qwirkler [COMMAND] [OPTIONS]
+
+Commands:
+    play          play interactively against the bots (default seats: human, then greedy)
+    selfplay      let the bots play each other and print the final boards (the default)
+    analyze       play one game and report every move next to the best available score
+    tournament    play several games, rotating seats, and rank the strategies
+    solve POS     print the best move for a position, eg '0,0=11 1,0=12 | 13 21 35'
+    serve         run an HTTP/JSON game server on localhost (see src/server.rs for the routes)
+    host          host multiplayer games over TCP, with bots in empty seats (see src/lobby.rs)
+    engine        speak the line based engine protocol on stdin/stdout (see src/engine.rs)
+    help          print this message
+
+Options:
+    --players N         number of seats (default: the number of strategies, or 2)
+    --strategies A,B    strategy for each seat: human (play only), greedy, random or search.
+                        A single strategy is used for every seat.  greedy:POLICY breaks
+                        ties between equal moves by first (greedy's default), random,
+                        longer or duplicates (keep duplicate pieces).  external:CMD runs CMD as
+                        an engine (see src/engine.rs) and swaps for it after an illegal
+                        reply; with external-strict:CMD an illegal reply forfeits the game.
+    --seed N            seed for the bag and any random strategies
+    --games N           number of games for selfplay and tournament
+    --format FORMAT     text (default) or json
+    --rules RULES       standard (default), or overrides such as hand=4,copies=2
+    --colour WHEN       draw boards with coloured shapes: auto (default), always or never
+    --svg DIR           selfplay only: also write an SVG frame of the board after every turn
+    --overlay WHAT      mark squares that can never be filled and lines that can never be
+                        completed on drawn boards: none (default) or dead
+    --port N            port for serve and host (default 8080)
+    --listen ADDR       host only: the address to accept players on (default 127.0.0.1, so
+                        only this machine); 0.0.0.0 accepts them on every interface
+    -h, --help          print this message";
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Options {
+    pub(crate) players: Option<usize>,
+    pub(crate) strategies: Vec<String>,
+    pub(crate) seed: Option<usize>,
+    pub(crate) games: Option<usize>,
+    pub(crate) format: Format,
+    pub(crate) rules: Rules,
+    pub(crate) colour: RenderMode,
+    pub(crate) overlay: Overlay,
+    pub(crate) svg_dir: Option<String>,
+    pub(crate) port: u16,
+    pub(crate) listen: IpAddr,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Command {
+    Help,
+    Play(Options),
+    SelfPlay(Options),
+    Analyze(Options),
+    Tournament(Options),
+    Solve(Options, String),
+    Serve(Options),
+    Host(Options),
+    Engine,
+}
+
+fn parse_number(flag: &str, value: &str, min: usize, max: usize) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(n) if n >= min && n <= max => Ok(n),
+        _ => Err(format!("{} expects a number between {} and {}, got '{}'", flag, min, max, value)),
+    }
+}
+
+/// Parses the command line (without the program name).
+pub fn parse_args(args: &[String]) -> Result<Command, String> {
+    let (command, rest) = match args.first() {
+        None => ("selfplay", &args[0..0]),
+        Some(first) if first.starts_with('-') => ("selfplay", args),
+        Some(first) => (first.as_ref(), &args[1..]),
+    };
+
+    let mut options = Options {
+        players: None,
+        strategies: vec![],
+        seed: None,
+        games: None,
+        format: Format::Text,
+        rules: Rules::standard(),
+        colour: RenderMode::auto(),
+        overlay: Overlay::Nothing,
+        svg_dir: None,
+        port: 8080,
+        listen: IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
+    };
+    let mut positional = vec![];
+    let mut i = 0;
+    while i < rest.len() {
+        let flag: &str = rest[i].as_ref();
+        if flag == "-h" || flag == "--help" {
+            return Ok(Command::Help);
+        }
+        if !flag.starts_with("--") {
+            positional.push(rest[i].clone());
+            i += 1;
+            continue;
+        }
+        let value: &str = match rest.get(i + 1) {
+            None => return Err(format!("{} expects a value", flag)),
+            Some(v) => v.as_ref(),
+        };
+        match flag {
+            "--players" => options.players = Some(parse_number(flag, value, 1, 6)?),
+            "--seed" => options.seed = Some(parse_number(flag, value, 0, usize::max_value())?),
+            "--games" => options.games = Some(parse_number(flag, value, 1, 1000000)?),
+            "--strategies" => {
+                options.strategies = value.split(',').map(|s| s.trim().to_string()).collect()
+            }
+            "--format" => {
+                options.format = match value {
+                    "text" => Format::Text,
+                    "json" => Format::Json,
+                    _ => return Err(format!("--format expects text or json, got '{}'", value)),
+                }
+            }
+            "--rules" => options.rules = Rules::parse(value)?,
+            "--colour" | "--color" => options.colour = RenderMode::parse(value)?,
+            "--overlay" => options.overlay = Overlay::parse(value)?,
+            "--svg" => options.svg_dir = Some(value.to_string()),
+            "--port" => options.port = parse_number(flag, value, 1, 65535)? as u16,
+            "--listen" => {
+                options.listen = match value.parse() {
+                    Ok(address) if command == "host" => address,
+                    Ok(_) => return Err("--listen only applies to host".to_string()),
+                    Err(_) => return Err(format!("--listen expects an IP address, got '{}'", value)),
+                }
+            }
+            _ => return Err(format!("unknown option '{}'", flag)),
+        }
+        i += 2;
+    }
+
+    if command == "solve" {
+        if positional.len() != 1 {
+            return Err("solve expects exactly one position".to_string());
+        }
+        return Ok(Command::Solve(options, positional.remove(0)));
+    }
+    if positional.len() > 0 {
+        return Err(format!("unexpected argument '{}'", positional[0]));
+    }
+    if options.svg_dir.is_some() && command != "selfplay" {
+        return Err("--svg only applies to selfplay".to_string());
+    }
+    if command == "play" && options.strategies.len() == 0 {
+        // a human in the first seat, and greedy bots in the rest
+        let mut names = vec!["human".to_string()];
+        names.resize(options.seats(), "greedy".to_string());
+        options.strategies = names;
+    }
+    for name in &options.strategies {
+        let known = strategy::NAMES.contains(&name.as_ref()) ||
+                    (command == "play" && name == "human") ||
+                    (name.starts_with("greedy:") &&
+                     gamestate::TIE_BREAKS.contains(&&name["greedy:".len()..])) ||
+                    name.starts_with("external:") || name.starts_with("external-strict:");
+        if !known {
+            return Err(format!("unknown strategy '{}' for {}", name, command));
+        }
+    }
+    match (options.players, options.strategies.len()) {
+        (Some(players), n) if n > 1 && n != players => {
+            return Err(format!("--players is {} but {} strategies were given", players, n))
+        }
+        _ => {}
+    }
+    if options.seats() * options.rules.hand_size > 36 * options.rules.copies {
+        return Err("there aren't enough pieces to fill every hand".to_string());
+    }
+
+    match command {
+        "help" => Ok(Command::Help),
+        "play" => Ok(Command::Play(options)),
+        "selfplay" => Ok(Command::SelfPlay(options)),
+        "analyze" => Ok(Command::Analyze(options)),
+        "tournament" => Ok(Command::Tournament(options)),
+        "serve" => Ok(Command::Serve(options)),
+        "host" => Ok(Command::Host(options)),
+        "engine" => Ok(Command::Engine),
+        _ => Err(format!("unknown command '{}'", command)),
+    }
+}
+
+impl Options {
+    fn seats(&self) -> usize {
+        match self.players {
+            Some(players) => players,
+            None if self.strategies.len() > 1 => self.strategies.len(),
+            None => 2,
+        }
+    }
+
+    /// The strategy name for every seat.
+    fn seat_strategies(&self) -> Vec<String> {
+        let seats = self.seats();
+        match self.strategies.len() {
+            0 => vec!["greedy".to_string(); seats],
+            1 => vec![self.strategies[0].clone(); seats],
+            _ => self.strategies.clone(),
+        }
+    }
+
+    fn seed(&self) -> usize {
+        return self.seed.unwrap_or_else(|| thread_rng().gen());
+    }
+}
+
+fn make_strategies(names: &[String], seed: usize) -> Vec<Box<dyn Strategy>> {
+    return names.iter()
+                .enumerate()
+                .map(|(seat, name)| strategy::from_name(name, seed.wrapping_add(seat + 1)).unwrap())
+                .collect();
+}
+
+/// Plays a game to the end, calling `on_move` before each move is applied.
+fn play_out<F: FnMut(&GameState, &Move)>(game_state: &mut GameState,
+                                         strategies: &mut [Box<dyn Strategy>],
+                                         mut on_move: F) {
+    while !game_state.is_over() {
+        let chosen_move = strategies[game_state.turn].choose_move(game_state);
+        on_move(game_state, &chosen_move);
+        game_state.apply_move(&chosen_move);
+    }
+}
+
+fn scores(game_state: &GameState) -> Vec<usize> {
+    return game_state.players.iter().map(|p| p.score).collect();
+}
+
+fn json_scores(game_state: &GameState) -> Json {
+    return Json::Array(scores(game_state).iter().map(|&s| Json::Int(s as i64)).collect());
+}
+
+pub fn run<W: Write>(command: Command, output: &mut W) -> io::Result<()> {
+    match command {
+        Command::Help => writeln!(output, "{}", USAGE),
+        Command::Play(options) => play(&options, output),
+        Command::SelfPlay(options) => self_play(&options, output),
+        Command::Analyze(options) => analyze(&options, output),
+        Command::Tournament(options) => tournament(&options, output),
+        Command::Solve(options, position) => solve(&options, &position, output),
+        Command::Engine => {
+            let stdin = io::stdin();
+            engine::run(&mut stdin.lock(), output)
+        }
+        Command::Serve(options) => {
+            writeln!(output, "serving on http://127.0.0.1:{}/", options.port)?;
+            output.flush()?;
+            server::serve(options.port)
+        }
+        Command::Host(options) => {
+            let listener = TcpListener::bind((options.listen, options.port))?;
+            writeln!(output, "hosting games on {}", listener.local_addr()?)?;
+            output.flush()?;
+            let bot = options.seat_strategies()[0].clone();
+            lobby::host(listener, Lobby::new(&bot, options.seed()))
+        }
+    }
+}
+
+fn play<W: Write>(options: &Options, output: &mut W) -> io::Result<()> {
+    let seed = options.seed();
+    let names = options.seat_strategies();
+    let mut seats: Vec<Seat> = names.iter()
+                                    .enumerate()
+                                    .map(|(seat, name)| {
+                                        match strategy::from_name(name, seed.wrapping_add(seat + 1)) {
+                                            Ok(strategy) => Seat::Bot(strategy),
+                                            Err(_) => Seat::Human,
+                                        }
+                                    })
+                                    .collect();
+    let mut game_state = GameState::with_rules(seats.len(), options.rules, seed);
+    writeln!(output, "seed = {}", seed)?;
+    let stdin = io::stdin();
+    return interactive::play(&mut game_state,
+                             &mut seats,
+                             options.colour,
+                             options.overlay,
+                             &mut stdin.lock(),
+                             output);
+}
+
+fn self_play<W: Write>(options: &Options, output: &mut W) -> io::Result<()> {
+    let seed = options.seed();
+    let names = options.seat_strategies();
+    let mut results = vec![];
+    for game in 0..options.games.unwrap_or(1) {
+        let game_seed = seed.wrapping_add(game);
+        let mut game_state = GameState::with_rules(names.len(), options.rules, game_seed);
+        let mut strategies = make_strategies(&names, game_seed);
+        let mut last_squares = vec![];
+        play_out(&mut game_state,
+                 &mut strategies,
+                 |_, chosen_move| last_squares = chosen_move.squares());
+        if let Some(ref dir) = options.svg_dir {
+            write_svg_frames(Path::new(dir), game_seed, &game_state.history, options)?;
+        }
+
+        match options.format {
+            Format::Text => {
+                let board = &game_state.board;
+                let dead = options.overlay.dead_spots(board, options.rules.copies);
+                writeln!(output,
+                         "{}",
+                         render::render_with_dead(board, &last_squares, &dead, options.colour))?;
+                writeln!(output,
+                         "Game finished (seed = {}), scores = {:?}, total score = {}\n",
+                         game_seed,
+                         scores(&game_state),
+                         game_state.total_score())?;
+            }
+            Format::Json => {
+                results.push(Json::object(vec![("seed", Json::Int(game_seed as i64)),
+                                               ("scores", json_scores(&game_state)),
+                                               ("total", Json::Int(game_state.total_score() as i64))]));
+            }
+        }
+    }
+    if options.format == Format::Json {
+        writeln!(output, "{}", Json::Array(results))?;
+    }
+    return Ok(());
+}
+
+/// Writes `<dir>/game-<seed>-turn-<n>.svg` for every turn of a game.
+fn write_svg_frames(dir: &Path, seed: usize, history: &[Move], options: &Options) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let frames = svg::replay_to_svg_frames(history, options.overlay, options.rules.copies);
+    for (turn, frame) in frames.iter().enumerate() {
+        let path = dir.join(format!("game-{}-turn-{:03}.svg", seed, turn + 1));
+        fs::File::create(path)?.write_all(frame.as_bytes())?;
+    }
+    return Ok(());
+}
+
+fn analyze<W: Write>(options: &Options, output: &mut W) -> io::Result<()> {
+    let seed = options.seed();
+    let names = options.seat_strategies();
+    let mut game_state = GameState::with_rules(names.len(), options.rules, seed);
+    let mut strategies = make_strategies(&names, seed);
+
+    let mut turns = vec![];
+    play_out(&mut game_state, &mut strategies, |state, chosen_move| {
+        let best = state.hints(1).pop();
+        turns.push((state.turn, chosen_move.clone(), best));
+    });
+    let best_score = |best: &Option<Hint>| best.as_ref().map_or(0, |hint| hint.chosen_move.score());
+
+    match options.format {
+        Format::Text => {
+            writeln!(output, "seed = {}", seed)?;
+            writeln!(output, "turn player strategy  score  best  move")?;
+            for (i, &(player, ref chosen_move, ref best)) in turns.iter().enumerate() {
+                // the move is followed by its breakdown, eg `0,0 R 11 12 (2 = 2 (main))`
+                write!(output,
+                       "{:>4} {:>6} {:<9} {:>5} {:>5}  {}",
+                       i + 1,
+                       player + 1,
+                       names[player],
+                       chosen_move.score(),
+                       best_score(best),
+                       chosen_move)?;
+                match *best {
+                    Some(ref hint) if hint.chosen_move.score() > chosen_move.score() => {
+                        writeln!(output,
+                                 "  (best was {}, {})",
+                                 notation::format_move(&hint.chosen_move),
+                                 hint.breakdown())?
+                    }
+                    _ => writeln!(output, "")?,
+                }
+            }
+            writeln!(output,
+                     "Game finished, scores = {:?}, total score = {}",
+                     scores(&game_state),
+                     game_state.total_score())?;
+        }
+        Format::Json => {
+            let json_turns = turns.iter()
+                                  .map(|&(player, ref chosen_move, ref best)| {
+                                      let mut turn = vec![("player", Json::Int(player as i64)),
+                                                          ("move", Json::Str(notation::format_move(chosen_move))),
+                                                          ("score", Json::Int(chosen_move.score() as i64)),
+                                                          ("best", Json::Int(best_score(best) as i64))];
+                                      if let Move::PlacePieces(_, _, _, ref breakdown) = *chosen_move {
+                                          turn.push(("breakdown", server::breakdown_json(breakdown)));
+                                      }
+                                      if let Some(ref hint) = *best {
+                                          turn.push(("best_move",
+                                                     Json::Str(notation::format_move(&hint.chosen_move))));
+                                          turn.push(("best_breakdown", server::breakdown_json(&hint.breakdown())));
+                                      }
+                                      Json::object(turn)
+                                  })
+                                  .collect();
+            writeln!(output,
+                     "{}",
+                     Json::object(vec![("seed", Json::Int(seed as i64)),
+                                       ("turns", Json::Array(json_turns)),
+                                       ("scores", json_scores(&game_state))]))?;
+        }
+    }
+    return Ok(());
+}
+
+fn tournament<W: Write>(options: &Options, output: &mut W) -> io::Result<()> {
+    let seed = options.seed();
+    let names = options.seat_strategies();
+    let seats = names.len();
+    let games = options.games.unwrap_or(10);
+    // wins are shared between tied players, so they are counted in halves etc
+    let mut wins = vec![0.0; seats];
+    let mut totals = vec![0; seats];
+
+    for game in 0..games {
+        // rotate the seating so each strategy takes turns at going first
+        let order: Vec<usize> = (0..seats).map(|seat| (seat + game) % seats).collect();
+        let seated: Vec<String> = order.iter().map(|&i| names[i].clone()).collect();
+        let game_seed = seed.wrapping_add(game);
+        let mut game_state = GameState::with_rules(seats, options.rules, game_seed);
+        let mut strategies = make_strategies(&seated, game_seed);
+        play_out(&mut game_state, &mut strategies, |_, _| {});
+
+        // a seat that forfeited can't win, but its points still count towards the average
+        let final_scores = scores(&game_state);
+        let in_play = |seat: usize| !strategies[seat].forfeited();
+        let top = (0..seats).filter(|&s| in_play(s)).map(|s| final_scores[s]).max();
+        let winners = (0..seats).filter(|&s| in_play(s) && Some(final_scores[s]) == top).count();
+        for (seat, &score) in final_scores.iter().enumerate() {
+            totals[order[seat]] += score;
+            if in_play(seat) && Some(score) == top {
+                wins[order[seat]] += 1.0 / winners as f64;
+            }
+        }
+    }
+
+    let mut ranking: Vec<usize> = (0..seats).collect();
+    ranking.sort_by(|&a, &b| wins[b].partial_cmp(&wins[a]).unwrap().then(totals[b].cmp(&totals[a])));
+    match options.format {
+        Format::Text => {
+            writeln!(output, "seed = {}, games = {}", seed, games)?;
+            // names like greedy:duplicates need a wider column
+            let width = names.iter().map(|name| name.len()).max().unwrap_or(0).max(9);
+            writeln!(output,
+                     "seat {:<width$} {:>7} {:>8}",
+                     "strategy",
+                     "wins",
+                     "average",
+                     width = width)?;
+            for &i in &ranking {
+                writeln!(output,
+                         "{:>4} {:<width$} {:>7.1} {:>8.1}",
+                         i + 1,
+                         names[i],
+                         wins[i],
+                         totals[i] as f64 / games as f64,
+                         width = width)?;
+            }
+        }
+        Format::Json => {
+            let standings = ranking.iter()
+                                   .map(|&i| {
+                                       Json::object(vec![("seat", Json::Int(i as i64)),
+                                                         ("strategy", Json::str(&names[i])),
+                                                         ("wins", Json::Float(wins[i])),
+                                                         ("average",
+                                                          Json::Float(totals[i] as f64 /
+                                                                      games as f64))])
+                                   })
+                                   .collect();
+            writeln!(output,
+                     "{}",
+                     Json::object(vec![("seed", Json::Int(seed as i64)),
+                                       ("games", Json::Int(games as i64)),
+                                       ("standings", Json::Array(standings))]))?;
+        }
+    }
+    return Ok(());
+}
+
+fn solve<W: Write>(options: &Options, position: &str, output: &mut W) -> io::Result<()> {
+    let game_state = match notation::parse_position(position).and_then(|(tiles, hand)| {
+        GameState::from_position(&tiles, hand, options.rules, options.seed())
+    }) {
+        Ok(game_state) => game_state,
+        Err(reason) => return Err(io::Error::new(io::ErrorKind::InvalidInput, reason)),
+    };
+    let best = game_state.generate_best_move().unwrap_or(Move::SwapPieces);
+    match options.format {
+        Format::Text => {
+            match best {
+                Move::SwapPieces => writeln!(output, "swap (scores 0)"),
+                Move::PlacePieces(_, _, _, ref breakdown) => {
+                    writeln!(output, "{} (scores {})", notation::format_move(&best), breakdown)
+                }
+            }
+        }
+        Format::Json => writeln!(output, "{}", server::move_json(&best)),
+    }
+}
+
+#[cfg(test)]
+fn args(s: &str) -> Vec<String> {
+    return s.split_whitespace().map(|a| a.to_string()).collect();
+}
+
+#[test]
+fn parse_args_should_default_to_selfplay() {
+    match parse_args(&args("")) {
+        Ok(Command::SelfPlay(options)) => assert!(options.seat_strategies().len() == 2),
+        _ => panic!("expected selfplay"),
+    }
+}
+
+#[test]
+fn parse_args_should_read_options() {
+    match parse_args(&args("tournament --strategies greedy,random --seed 7 --games 3 \
+                            --format json --rules hand=4 --overlay dead")) {
+        Ok(Command::Tournament(options)) => {
+            assert!(options.seat_strategies() == vec!["greedy", "random"]);
+            assert!(options.seed == Some(7) && options.games == Some(3));
+            assert!(options.format == Format::Json && options.rules.hand_size == 4);
+            assert!(options.overlay == Overlay::Dead);
+        }
+        _ => panic!("expected tournament"),
+    }
+}
+
+#[test]
+fn parse_args_should_seat_greedy_bots_after_the_human() {
+    match parse_args(&args("play --players 3")) {
+        Ok(Command::Play(options)) => assert!(options.seat_strategies() == vec!["human", "greedy", "greedy"]),
+        _ => panic!("expected play"),
+    }
+}
+
+#[test]
+fn parse_args_should_reject_invalid_values() {
+    assert!(parse_args(&args("selfplay --players 0")).is_err());
+    assert!(parse_args(&args("selfplay --players 3 --strategies greedy,random")).is_err());
+    assert!(parse_args(&args("selfplay --strategies human")).is_err());
+    assert!(parse_args(&args("selfplay --strategies greedy:last")).is_err());
+    assert!(parse_args(&args("selfplay --strategies greedy:longer,greedy:random")).is_ok());
+    assert!(parse_args(&args("selfplay --format xml")).is_err());
+    assert!(parse_args(&args("selfplay --overlay everything")).is_err());
+    assert!(parse_args(&args("selfplay --seed")).is_err());
+    assert!(parse_args(&args("fly")).is_err());
+    assert!(parse_args(&args("solve")).is_err());
+    assert!(parse_args(&args("host --listen everywhere")).is_err());
+    assert!(parse_args(&args("serve --listen 0.0.0.0")).is_err());
+}
+
+#[test]
+fn parse_args_should_host_on_localhost_unless_told_otherwise() {
+    match (parse_args(&args("host")), parse_args(&args("host --listen 0.0.0.0"))) {
+        (Ok(Command::Host(local)), Ok(Command::Host(everywhere))) => {
+            assert!(local.listen.is_loopback());
+            assert!(everywhere.listen.is_unspecified());
+        }
+        _ => panic!("expected host"),
+    }
+}
+
+#[test]
+fn solve_should_print_the_best_move() {
+    let command = parse_args(&["solve".to_string(), "0,0=11 1,0=12 | 13 14 25".to_string()]);
+    let mut output = vec![];
+    run(command.unwrap(), &mut output).unwrap();
+    // 13 14 underneath 12 11 forms three lines of two
+    assert!(String::from_utf8(output).unwrap().contains("(scores 6 = 2 (main) + 2 (perp) + 2 (perp))"));
+}
+
+#[test]
+fn analyze_should_write_breakdowns_as_json() {
+    let command = parse_args(&args("analyze --seed 5 --format json"));
+    let mut output = vec![];
+    run(command.unwrap(), &mut output).unwrap();
+    let report = Json::parse(&String::from_utf8(output).unwrap()).unwrap();
+    let turns = report.get("turns").and_then(|t| t.as_array()).unwrap();
+    let breakdown = turns[0].get("breakdown").and_then(|b| b.as_array()).unwrap();
+    let points: i64 = breakdown.iter()
+                               .map(|line| line.get("points").and_then(|p| p.as_i64()).unwrap())
+                               .sum();
+    assert!(turns[0].get("score") == Some(&Json::Int(points)));
+    assert!(turns[0].get("best_breakdown").and_then(|b| b.as_array()).is_some());
+}
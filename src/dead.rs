@@ -0,0 +1,143 @@
+//! Squares that can never be filled and lines that can never be completed.
+//!
+//! A square is dead when no piece that is still available fits both its row and its column.
+//! The rows and columns alone can rule out every piece (see `Board::blocked_squares`), but so
+//! can the tile supply: with three copies of each piece, once all three copies of the only
+//! piece that fits are on the board, nothing can go there.  Lines only grow and pieces never
+//! leave the board, so anything dead stays dead.
+//!
+//! A line is dead when it can never become a Qwirkle.  It needs every other piece of its colour
+//! (or shape), so it is dead if one of those has run out, or if neither end of the line can
+//! take any of them.
+
+use board::{Board, Line, LineAttribute};
+use direction::{Direction, Square};
+use piece::Piece;
+
+/// The dead squares and lines of a board, from `DeadSpots::find`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DeadSpots {
+    /// Empty squares next to the pieces that no piece can ever be placed on, row by row.
+    pub squares: Vec<Square>,
+    /// Lines of fewer than six pieces that can never be completed, in `Board::lines` order.
+    pub lines: Vec<Line>,
+}
+
+impl DeadSpots {
+    /// Looks for dead squares and lines, given that the game has `copies` of every piece (see
+    /// `Rules::copies`).
+    pub fn find(board: &Board, copies: usize) -> DeadSpots {
+        let mut on_board = [0; 36];
+        for (_, piece) in board.occupied() {
+            on_board[piece.index()] += 1;
+        }
+        let available = (0..36).filter(|&i| on_board[i] < copies).fold(0u64, |mask, i| mask | 1 << i);
+
+        let mut squares: Vec<Square> = board.perimeter()
+                                            .iter()
+                                            .cloned()
+                                            .filter(|&sq| fits(board, sq) & available == 0)
+                                            .collect();
+        squares.sort_by_key(|&(x, y)| (y, x));
+
+        let lines = board.lines()
+                         .into_iter()
+                         .filter(|line| line.pieces.len() < 6 && !can_complete(board, line, available))
+                         .collect();
+        return DeadSpots {
+            squares: squares,
+            lines: lines,
+        };
+    }
+
+    /// True if `sq` is empty and can never be filled.
+    pub fn is_dead_square(&self, sq: Square) -> bool {
+        return self.squares.contains(&sq);
+    }
+
+    /// True if `sq` holds a piece of a line that can never be completed.
+    pub fn in_dead_line(&self, sq: Square) -> bool {
+        return self.lines.iter().any(|line| line.squares().contains(&sq));
+    }
+}
+
+/// The pieces that both the row and the column through the empty square `sq` allow there, as a
+/// bitmask of `Piece::index()`es.
+fn fits(board: &Board, sq: Square) -> u64 {
+    if !board.in_bounds(sq) {
+        return 0;
+    }
+    return board.cross_check(sq, &Direction::R).allowed & board.cross_check(sq, &Direction::U).allowed;
+}
+
+/// Whether `line` could still grow into a Qwirkle from the pieces in `available`.
+fn can_complete(board: &Board, line: &Line, available: u64) -> bool {
+    let attribute = match line.attribute {
+        None => return false,
+        Some(attribute) => attribute,
+    };
+    let missing = (0..36)
+                      .map(Piece::from_index)
+                      .filter(|piece| {
+                          let shared = match attribute {
+                              LineAttribute::Colour(colour) => piece.colour == colour,
+                              LineAttribute::Shape(shape) => piece.shape == shape,
+                          };
+                          shared && !line.pieces.contains(piece)
+                      })
+                      .fold(0u64, |mask, piece| mask | 1 << piece.index());
+    if missing & !available != 0 {
+        return false;
+    }
+    let before = line.direction.opposite().apply(line.start);
+    let after = line.direction.apply(*line.squares().last().unwrap());
+    return (fits(board, before) | fits(board, after)) & missing != 0;
+}
+
+#[cfg(test)]
+use piece::{Colour, Shape};
+
+/// Every red piece: 11 to 15 along row 0 from 0,0, and 16 under 11.  With one copy of each
+/// piece, both red lines and the squares only a red piece fits are dead.  The render and SVG
+/// overlay tests draw this board too.
+#[cfg(test)]
+pub fn every_red_piece() -> Board {
+    let mut board = Board::new();
+    let red = |shape| Piece::new(Colour::R, shape);
+    board.put((0, 0),
+              &Direction::R,
+              &vec![red(Shape::A), red(Shape::B), red(Shape::C), red(Shape::D), red(Shape::E)]);
+    board.put((0, 1), &Direction::U, &vec![red(Shape::F)]);
+    return board;
+}
+
+#[test]
+fn find_should_mark_squares_and_lines_the_supply_has_run_out_for() {
+    let board = every_red_piece();
+
+    // with three copies of everything there are two more of 16 for the ends of the row
+    assert!(DeadSpots::find(&board, 3) == DeadSpots::default());
+
+    // with one, every red piece is on the board, so neither red line can be finished, and the
+    // squares that only take red pieces (the ends of both lines, and 1,1 between them) can't
+    // be filled
+    let dead = DeadSpots::find(&board, 1);
+    assert!(dead.squares == vec![(0, -1), (-1, 0), (5, 0), (1, 1), (0, 2)]);
+    assert!(dead.lines.len() == 2);
+    assert!(dead.in_dead_line((4, 0)) && dead.in_dead_line((0, 1)));
+    assert!(dead.is_dead_square((5, 0)) && !dead.is_dead_square((2, 1)));
+}
+
+#[test]
+fn find_should_mark_lines_with_both_ends_blocked() {
+    let mut board = Board::new();
+    let piece = |colour, shape| Piece::new(colour, shape);
+    board.put((0, 0), &Direction::R, &vec![piece(Colour::R, Shape::A), piece(Colour::R, Shape::B)]);
+    // a column of circles ends at each end of the row, and only a red circle would fit there
+    board.put((-1, 1), &Direction::U, &vec![piece(Colour::O, Shape::A), piece(Colour::Y, Shape::A)]);
+    board.put((2, 1), &Direction::U, &vec![piece(Colour::G, Shape::A), piece(Colour::B, Shape::A)]);
+
+    let dead = DeadSpots::find(&board, 3);
+    assert!(dead.squares == vec![(-1, 0), (2, 0)]);
+    assert!(dead.lines.len() == 1 && dead.lines[0].start == (0, 0));
+}
@@ -0,0 +1,234 @@
+use direction::Square;
+use gamestate::GameState;
+use notation;
+use piece::{Bag, Piece};
+use rules::Rules;
+use search::Search;
+use strategy;
+use std::io::{self, BufRead, Write};
+use std::time::{Duration, Instant};
+
+/// A line based engine protocol over stdin/stdout, in the spirit of UCI for chess, so that
+/// GUIs and tournament managers can drive qwirkler as a subprocess.
+///
+/// The host sends one command per line:
+///
+/// * `qwirkle` starts the session.  The engine replies with `id name ...`, `id author ...`,
+///   an `option ...` line for every setting and finally `qwirkleok`.
+/// * `isready` is answered with `readyok`.
+/// * `setoption name <name> value <value>` changes a setting:
+///   `strategy` (`greedy`, `random` or `search`) or `seed` (a number).
+/// * `newgame [rules]` clears the board and hand.  Rules are written as for `--rules`,
+///   eg `newgame hand=4,copies=2`; the default is `standard`.
+/// * `position <position>` sets the board and the hand, eg `position 0,0=11 1,0=12 | 13 21 35`.
+///   `position empty` clears the board but keeps the hand.
+/// * `hand <pieces>` replaces the hand, eg `hand 13 21 35`.
+/// * `scores <n> ...` and `bag <n>` give every player's score and the number of pieces left in
+///   the bag.  They are for information only; qwirkler's own strategies ignore them.
+/// * `go [movetime <ms>]` picks a move.  The engine replies with an `info` line, eg
+///   `info depth 2 nodes 96 samples 11 score 4.5 time 500 pv 1,1 L 13 14`, and then
+///   `bestmove <move>`, where the move is in move notation, `swap`, or `none` when the bag and
+///   board leave nothing to do.  `movetime` only affects the `search` strategy (default 100).
+/// * `quit` ends the session.
+///
+/// Any pieces not on the board or in the hand are assumed to be in the bag or in opponents'
+/// hands.  Malformed commands are answered with `info string error: <reason>`.
+pub struct Engine {
+    tiles: Vec<(Square, Piece)>,
+    hand: Bag,
+    rules: Rules,
+    strategy: String,
+    seed: usize,
+}
+
+impl Engine {
+    pub fn new() -> Engine {
+        return Engine {
+            tiles: vec![],
+            hand: vec![],
+            rules: Rules::standard(),
+            strategy: "greedy".to_string(),
+            seed: 0,
+        };
+    }
+
+    /// Handles one command line, returning the reply lines and whether the session should end.
+    pub fn handle(&mut self, line: &str) -> (Vec<String>, bool) {
+        let line = line.trim();
+        let (command, args) = match line.find(' ') {
+            None => (line, ""),
+            Some(i) => (&line[..i], line[i + 1..].trim()),
+        };
+        let reply = match command {
+            "" => Ok(vec![]),
+            "quit" => return (vec![], true),
+            "qwirkle" => {
+                Ok(vec!["id name qwirkler".to_string(),
+                        "id author Dan Fox".to_string(),
+                        format!("option name strategy type combo default greedy {}",
+                                strategy::NAMES.iter()
+                                               .map(|n| format!("var {}", n))
+                                               .collect::<Vec<_>>()
+                                               .join(" ")),
+                        "option name seed type spin default 0".to_string(),
+                        "qwirkleok".to_string()])
+            }
+            "isready" => Ok(vec!["readyok".to_string()]),
+            "setoption" => self.set_option(args).map(|_| vec![]),
+            "newgame" => {
+                let spec = if args.len() == 0 { "standard" } else { args };
+                Rules::parse(spec).map(|rules| {
+                    self.rules = rules;
+                    self.tiles.clear();
+                    self.hand.clear();
+                    vec![]
+                })
+            }
+            "position" if args == "empty" => {
+                self.tiles.clear();
+                Ok(vec![])
+            }
+            "position" => {
+                notation::parse_position(args).map(|(tiles, hand)| {
+                    self.tiles = tiles;
+                    self.hand = hand;
+                    vec![]
+                })
+            }
+            "hand" => {
+                args.split_whitespace()
+                    .map(notation::parse_piece)
+                    .collect::<Result<Bag, String>>()
+                    .map(|hand| {
+                        self.hand = hand;
+                        vec![]
+                    })
+            }
+            "scores" | "bag" => Ok(vec![]),
+            "go" => self.go(args),
+            _ => Err(format!("unknown command '{}'", command)),
+        };
+        match reply {
+            Ok(lines) => (lines, false),
+            Err(reason) => (vec![format!("info string error: {}", reason)], false),
+        }
+    }
+
+    fn set_option(&mut self, args: &str) -> Result<(), String> {
+        let tokens: Vec<&str> = args.split_whitespace().collect();
+        if tokens.len() != 4 || tokens[0] != "name" || tokens[2] != "value" {
+            return Err("expected setoption name <name> value <value>".to_string());
+        }
+        match tokens[1] {
+            "strategy" if strategy::NAMES.contains(&tokens[3]) => {
+                self.strategy = tokens[3].to_string();
+                Ok(())
+            }
+            "strategy" => Err(format!("unknown strategy '{}'", tokens[3])),
+            "seed" => {
+                match tokens[3].parse() {
+                    Ok(seed) => {
+                        self.seed = seed;
+                        Ok(())
+                    }
+                    Err(_) => Err(format!("'{}' is not a seed", tokens[3])),
+                }
+            }
+            _ => Err(format!("unknown option '{}'", tokens[1])),
+        }
+    }
+
+    fn go(&mut self, args: &str) -> Result<Vec<String>, String> {
+        let tokens: Vec<&str> = args.split_whitespace().collect();
+        let movetime = match tokens.as_slice() {
+            [] => Duration::from_millis(100),
+            ["movetime", ms] => {
+                match ms.parse() {
+                    Ok(ms) => Duration::from_millis(ms),
+                    Err(_) => return Err(format!("'{}' is not a number of milliseconds", ms)),
+                }
+            }
+            _ => return Err("expected go [movetime <ms>]".to_string()),
+        };
+        let game_state = GameState::from_position(&self.tiles, self.hand.clone(), self.rules, self.seed)?;
+        if game_state.is_over() {
+            return Ok(vec!["bestmove none".to_string()]);
+        }
+
+        let start = Instant::now();
+        let (chosen_move, info) = if self.strategy == "search" {
+            let mut search = Search::new(movetime, self.seed);
+            let chosen_move = strategy::Strategy::choose_move(&mut search, &game_state);
+            let stats = search.stats;
+            (chosen_move,
+             format!("info depth {} nodes {} samples {} score {:.1}",
+                     stats.depth,
+                     stats.nodes,
+                     stats.samples,
+                     stats.value))
+        } else {
+            let mut chosen = strategy::from_name(&self.strategy, self.seed)?;
+            let chosen_move = chosen.choose_move(&game_state);
+            let score = chosen_move.score();
+            (chosen_move, format!("info depth 1 score {}", score))
+        };
+        let text = notation::format_move(&chosen_move);
+        let elapsed = start.elapsed();
+        let millis = elapsed.as_secs() * 1000 + (elapsed.subsec_nanos() / 1000000) as u64;
+        return Ok(vec![format!("{} time {} pv {}", info, millis, text), format!("bestmove {}", text)]);
+    }
+}
+
+/// Runs the protocol until `quit` or the end of `input`.
+pub fn run<R: BufRead, W: Write>(input: &mut R, output: &mut W) -> io::Result<()> {
+    let mut engine = Engine::new();
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        let (replies, quit) = engine.handle(&line);
+        for reply in replies {
+            writeln!(output, "{}", reply)?;
+        }
+        output.flush()?;
+        if quit {
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(test)]
+fn session(commands: &str) -> Vec<String> {
+    let mut output = vec![];
+    run(&mut io::Cursor::new(commands.as_bytes()), &mut output).unwrap();
+    return String::from_utf8(output).unwrap().lines().map(|l| l.to_string()).collect();
+}
+
+#[test]
+fn engine_should_handshake_and_play_a_position() {
+    let lines = session("qwirkle\nisready\nnewgame\nposition 0,0=11 1,0=12 | 13 14 25\ngo\nquit\ngo\n");
+    assert!(lines[0] == "id name qwirkler");
+    assert!(lines.contains(&"qwirkleok".to_string()));
+    assert!(lines.contains(&"readyok".to_string()));
+    assert!(lines[lines.len() - 2].starts_with("info depth 1 score 6 time "));
+    assert!(lines[lines.len() - 1].starts_with("bestmove "));
+}
+
+#[test]
+fn engine_should_search_with_a_movetime() {
+    let lines = session("setoption name strategy value search\nhand 11 12 13\ngo movetime 20\n");
+    assert!(lines[0].starts_with("info depth 2 nodes "));
+    assert!(lines[1].starts_with("bestmove "));
+    let chosen_move = notation::parse_move(&lines[1]["bestmove ".len()..]).unwrap();
+    assert!(chosen_move.squares().contains(&(0, 0)));
+}
+
+#[test]
+fn engine_should_report_errors() {
+    let lines = session("position 0,0=99 | 11\nsetoption name strategy value wizard\nfly\n\
+                         position 0,0=11 | 11 11 11\ngo\n");
+    assert!(lines.len() == 4);
+    assert!(lines.iter().all(|l| l.starts_with("info string error: ")));
+}
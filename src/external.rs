@@ -0,0 +1,259 @@
+use gamestate::GameState;
+use mv::Move;
+use notation;
+use strategy::{Greedy, Strategy};
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// What to do when an external engine replies with an illegal move, garbage, or nothing at all.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum IllegalReply {
+    /// Swap the whole hand instead (see `fallback`), and keep asking the engine on later turns.
+    Swap,
+    /// Stop talking to the engine and play `fallback` moves for the rest of the game.
+    /// `forfeited()` reports it, so a tournament can score the game as a loss.
+    Forfeit,
+}
+
+/// A strategy played by another program, which must speak the protocol in `engine.rs`.
+///
+/// The program is started on the first move.  Each turn it is sent the player's view of the
+/// game (`position`, `scores` and `bag`) followed by `go movetime <ms>`, and must answer with
+/// `bestmove <move>` within `timeout`.
+pub struct ExternalEngine {
+    command: String,
+    pub movetime: Duration,
+    pub timeout: Duration,
+    pub on_illegal: IllegalReply,
+    /// The reason the last reply was rejected, if it was.
+    pub last_error: Option<String>,
+    process: Option<Process>,
+    forfeited: bool,
+}
+
+struct Process {
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+}
+
+impl Process {
+    fn spawn(command: &str) -> Result<Process, String> {
+        let words: Vec<&str> = command.split_whitespace().collect();
+        if words.len() == 0 {
+            return Err("no command given".to_string());
+        }
+        let mut child = match Command::new(words[0])
+                                  .args(&words[1..])
+                                  .stdin(Stdio::piped())
+                                  .stdout(Stdio::piped())
+                                  .spawn() {
+            Ok(child) => child,
+            Err(e) => return Err(format!("couldn't start '{}': {}", command, e)),
+        };
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+
+        // Read on a separate thread so that we can give up on a silent engine.
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                match line {
+                    Ok(line) => {
+                        if sender.send(line).is_err() {
+                            return;
+                        }
+                    }
+                    Err(_) => return,
+                }
+            }
+        });
+        return Ok(Process {
+            child: child,
+            stdin: stdin,
+            lines: receiver,
+        });
+    }
+
+    fn send(&mut self, line: &str) -> Result<(), String> {
+        return writeln!(self.stdin, "{}", line)
+                   .and_then(|_| self.stdin.flush())
+                   .map_err(|e| format!("couldn't write to the engine: {}", e));
+    }
+
+    /// Returns the rest of the first line starting with `prefix`, skipping any others.
+    fn read_until(&mut self, prefix: &str, timeout: Duration) -> Result<String, String> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let now = Instant::now();
+            let remaining = if deadline > now { deadline - now } else { Duration::from_millis(0) };
+            match self.lines.recv_timeout(remaining) {
+                Ok(line) => {
+                    if line.starts_with(prefix) {
+                        return Ok(line[prefix.len()..].trim().to_string());
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    return Err(format!("no '{}' within {:?}", prefix.trim(), timeout))
+                }
+                Err(RecvTimeoutError::Disconnected) => return Err("the engine exited".to_string()),
+            }
+        }
+    }
+}
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+impl ExternalEngine {
+    pub fn new(command: &str, on_illegal: IllegalReply) -> ExternalEngine {
+        return ExternalEngine {
+            command: command.to_string(),
+            movetime: Duration::from_millis(100),
+            timeout: Duration::from_secs(5),
+            on_illegal: on_illegal,
+            last_error: None,
+            process: None,
+            forfeited: false,
+        };
+    }
+
+    fn ask(&mut self, game_state: &GameState) -> Result<Move, String> {
+        if self.process.is_none() {
+            let mut process = Process::spawn(&self.command)?;
+            process.send("qwirkle")?;
+            process.read_until("qwirkleok", self.timeout)?;
+            process.send(&format!("newgame {}", game_state.rules))?;
+            self.process = Some(process);
+        }
+        let process = self.process.as_mut().unwrap();
+
+        let view = game_state.view(game_state.turn);
+        let scores: Vec<String> = view.scores.iter().map(|s| s.to_string()).collect();
+        process.send(&format!("position {}", view.position()))?;
+        process.send(&format!("scores {}", scores.join(" ")))?;
+        process.send(&format!("bag {}", view.bag_size))?;
+        let millis = self.movetime.as_secs() * 1000 + (self.movetime.subsec_nanos() / 1000000) as u64;
+        process.send(&format!("go movetime {}", millis))?;
+        let reply = process.read_until("bestmove ", self.movetime + self.timeout)?;
+
+        let proposed = notation::parse_move(&reply)?;
+        return game_state.validate_move(&proposed)
+                         .map_err(|reason| format!("illegal move '{}': {}", reply, reason));
+    }
+}
+
+/// The move played for an engine that hasn't given a legal one: a swap while the bag has
+/// pieces, and the greedy move once it is empty and swapping is no longer allowed.
+fn fallback(game_state: &GameState) -> Move {
+    if game_state.bag_size() > 0 {
+        return Move::SwapPieces;
+    }
+    return Greedy.choose_move(game_state);
+}
+
+impl Strategy for ExternalEngine {
+    fn name(&self) -> &str {
+        return &self.command;
+    }
+
+    fn choose_move(&mut self, game_state: &GameState) -> Move {
+        if self.forfeited {
+            return fallback(game_state);
+        }
+        match self.ask(game_state) {
+            Ok(legal) => {
+                self.last_error = None;
+                return legal;
+            }
+            Err(reason) => {
+                let _ = writeln!(io::stderr(), "{}: {}", self.command, reason);
+                self.last_error = Some(reason);
+                if self.on_illegal == IllegalReply::Forfeit {
+                    self.forfeited = true;
+                    self.process = None;
+                }
+                return fallback(game_state);
+            }
+        }
+    }
+
+    fn forfeited(&self) -> bool {
+        return self.forfeited;
+    }
+}
+
+#[cfg(all(test, unix))]
+use rules::Rules;
+
+/// A shell script engine, saved as `name` in the temp directory, that answers every `go` by
+/// running `reply`.
+#[cfg(all(test, unix))]
+fn scripted(name: &str, reply: &str, on_illegal: IllegalReply) -> ExternalEngine {
+    let path = ::std::env::temp_dir().join(format!("qwirkler-{}-{}.sh", name, ::std::process::id()));
+    let script = format!("while read line; do case \"$line\" in qwirkle) echo qwirkleok;; \
+                          go*) {};; esac; done\n",
+                         reply);
+    ::std::fs::File::create(&path).unwrap().write_all(script.as_bytes()).unwrap();
+    let mut engine = ExternalEngine::new(&format!("sh {}", path.display()), on_illegal);
+    engine.timeout = Duration::from_millis(300);
+    return engine;
+}
+
+#[cfg(unix)]
+#[test]
+fn external_engine_should_play_legal_replies_and_swap_on_illegal_ones() {
+    let game_state = GameState::with_rules(2, Rules::standard(), 5);
+    let best = notation::format_move(&game_state.generate_best_move().unwrap());
+
+    let mut good = scripted("good",
+                            &format!("echo info depth 1; echo 'bestmove {}'", best),
+                            IllegalReply::Swap);
+    assert!(notation::format_move(&good.choose_move(&game_state)) == best);
+    assert!(good.last_error.is_none());
+
+    let mut bad = scripted("bad", "echo 'bestmove 40,40 R 11'", IllegalReply::Swap);
+    match bad.choose_move(&game_state) {
+        Move::SwapPieces => assert!(bad.last_error.is_some() && !bad.forfeited()),
+        _ => panic!("expected a swap"),
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn external_engine_should_forfeit_after_a_timeout() {
+    let game_state = GameState::with_rules(2, Rules::standard(), 5);
+    let mut silent = scripted("silent", "true", IllegalReply::Forfeit);
+    match silent.choose_move(&game_state) {
+        Move::SwapPieces => assert!(silent.forfeited()),
+        _ => panic!("expected a swap"),
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn external_engine_should_fall_back_to_a_legal_move_once_the_bag_is_empty() {
+    let mut game_state = GameState::with_rules(2, Rules::standard(), 5);
+    while game_state.bag_size() > 0 {
+        let chosen_move = game_state.generate_best_move().unwrap();
+        game_state.apply_move(&chosen_move);
+    }
+    assert!(!game_state.is_over());
+    for &on_illegal in &[IllegalReply::Swap, IllegalReply::Forfeit] {
+        let mut bad = scripted("empty-bag", "echo 'bestmove swap'", on_illegal);
+        for _ in 0..2 {
+            let chosen_move = bad.choose_move(&game_state);
+            assert!(game_state.validate_move(&chosen_move).is_ok());
+            assert!(chosen_move.squares().len() > 0);
+        }
+        assert!(bad.last_error.is_some() && bad.forfeited() == (on_illegal == IllegalReply::Forfeit));
+    }
+}
@@ -0,0 +1,362 @@
+//! A C ABI for embedding the engine in other languages.  `include/qwirkler.h` declares
+//! everything here; `tests/c/test_ffi.c` shows it in use.
+//!
+//! Games are opaque `QwirklerGame` handles, created with `qwirkler_game_new` and released with
+//! `qwirkler_game_free`.  Every `char *` returned by this library is owned by the caller and
+//! must be released with `qwirkler_string_free`.  Moves and positions use the notation in
+//! `notation.rs`, eg `0,0 R 11 12` or `swap`.
+//!
+//! Functions never unwind into C: a null or invalid argument gives a null pointer or a negative
+//! status, and `qwirkler_game_last_error` explains what went wrong.  Every body runs inside
+//! `catch_unwind`, so a panic (a bug in the engine) gives a null pointer, -1 or
+//! `QWIRKLER_INTERNAL_ERROR` instead.  A game that has hit one should only be freed.
+
+use gamestate::GameState;
+use json::Json;
+use notation;
+use rules::Rules;
+use search::Search;
+use server;
+use strategy::{Greedy, Strategy};
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::time::Duration;
+
+pub struct QwirklerGame {
+    state: GameState,
+    seed: u64,
+    last_error: String,
+}
+
+/// `qwirkler_game_apply_move` succeeded.
+pub const QWIRKLER_OK: c_int = 0;
+/// An argument was null or not valid UTF-8.
+pub const QWIRKLER_BAD_ARGUMENT: c_int = -1;
+/// The move couldn't be read.
+pub const QWIRKLER_BAD_NOTATION: c_int = -2;
+/// The move was read but isn't legal for the player whose turn it is.
+pub const QWIRKLER_ILLEGAL_MOVE: c_int = -3;
+/// The engine panicked.
+pub const QWIRKLER_INTERNAL_ERROR: c_int = -4;
+
+/// Runs `body`, returning `on_panic` if it panics rather than unwinding into C.
+fn guard<T, F: FnOnce() -> T>(on_panic: T, body: F) -> T {
+    return panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or(on_panic);
+}
+
+/// Null if `s` has a NUL byte in it, which our own output never does.
+fn to_c_string(s: &str) -> *mut c_char {
+    return CString::new(s).map(CString::into_raw).unwrap_or(ptr::null_mut());
+}
+
+unsafe fn from_c_string<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        return None;
+    }
+    return CStr::from_ptr(s).to_str().ok();
+}
+
+/// Creates a game for `players` seats.  `rules` is written as for `--rules`, eg
+/// `hand=4,copies=2`, or null for the standard rules.  The same seed always deals the same
+/// pieces.  Returns null if the rules can't be read or there aren't enough pieces for every hand.
+///
+/// # Safety
+///
+/// `rules` must be null or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn qwirkler_game_new(players: usize,
+                                           rules: *const c_char,
+                                           seed: u64)
+                                           -> *mut QwirklerGame {
+    guard(ptr::null_mut(), || {
+        let rules = if rules.is_null() {
+            Rules::standard()
+        } else {
+            match from_c_string(rules).map(Rules::parse) {
+                Some(Ok(rules)) => rules,
+                _ => return ptr::null_mut(),
+            }
+        };
+        if players == 0 || players * rules.hand_size > 36 * rules.copies {
+            return ptr::null_mut();
+        }
+        let game = QwirklerGame {
+            state: GameState::with_rules(players, rules, seed as usize),
+            seed: seed,
+            last_error: String::new(),
+        };
+        return Box::into_raw(Box::new(game));
+    })
+}
+
+/// Releases a game.
+///
+/// # Safety
+///
+/// `game` must be null or a handle from `qwirkler_game_new` that hasn't been freed, and
+/// mustn't be used again.
+#[no_mangle]
+pub unsafe extern "C" fn qwirkler_game_free(game: *mut QwirklerGame) {
+    guard((), || {
+        if !game.is_null() {
+            drop(Box::from_raw(game));
+        }
+    })
+}
+
+/// Releases a string returned by this library.
+///
+/// # Safety
+///
+/// `s` must be null or a string returned by this library that hasn't been freed, and mustn't
+/// be used again.
+#[no_mangle]
+pub unsafe extern "C" fn qwirkler_string_free(s: *mut c_char) {
+    guard((), || {
+        if !s.is_null() {
+            drop(CString::from_raw(s));
+        }
+    })
+}
+
+/// The player whose turn it is, or -1 for a null game.
+///
+/// # Safety
+///
+/// `game` must be null or a handle from `qwirkler_game_new` that hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn qwirkler_game_turn(game: *const QwirklerGame) -> c_int {
+    guard(-1, || {
+        match game.as_ref() {
+            None => -1,
+            Some(game) => game.state.turn as c_int,
+        }
+    })
+}
+
+/// 1 once the game has finished, 0 while it is running, or -1 for a null game.
+///
+/// # Safety
+///
+/// `game` must be null or a handle from `qwirkler_game_new` that hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn qwirkler_game_is_over(game: *const QwirklerGame) -> c_int {
+    guard(-1, || {
+        match game.as_ref() {
+            None => -1,
+            Some(game) => game.state.is_over() as c_int,
+        }
+    })
+}
+
+/// The game as seen by `player`, in the same JSON as the HTTP server's `GET /games/<id>`.
+/// Returns null if there is no such player.
+///
+/// # Safety
+///
+/// `game` must be null or a handle from `qwirkler_game_new` that hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn qwirkler_game_state_json(game: *const QwirklerGame, player: usize) -> *mut c_char {
+    guard(ptr::null_mut(), || {
+        match game.as_ref() {
+            Some(game) if player < game.state.players.len() => {
+                to_c_string(&game.state.view(player).to_json().to_string())
+            }
+            _ => ptr::null_mut(),
+        }
+    })
+}
+
+/// The board and `player`'s hand in position notation, eg `0,0=11 1,0=12 | 13 21 35`.
+/// Returns null if there is no such player.
+///
+/// # Safety
+///
+/// `game` must be null or a handle from `qwirkler_game_new` that hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn qwirkler_game_position(game: *const QwirklerGame, player: usize) -> *mut c_char {
+    guard(ptr::null_mut(), || {
+        match game.as_ref() {
+            Some(game) if player < game.state.players.len() => {
+                to_c_string(&game.state.view(player).position())
+            }
+            _ => ptr::null_mut(),
+        }
+    })
+}
+
+/// Every legal placement for the player whose turn it is, as a JSON array of
+/// `{"move": "0,0 R 11 12", "score": 2, "breakdown": [...]}` objects, where the breakdown lists
+/// each line scored as `{"kind": "main", "x": 0, "y": 0, "length": 2, "qwirkle": false,
+/// "points": 2}`.  Swapping isn't listed.
+///
+/// # Safety
+///
+/// `game` must be null or a handle from `qwirkler_game_new` that hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn qwirkler_game_legal_moves(game: *const QwirklerGame) -> *mut c_char {
+    guard(ptr::null_mut(), || {
+        match game.as_ref() {
+            None => ptr::null_mut(),
+            Some(game) => {
+                let moves = game.state.generate_moves().iter().map(server::move_json).collect();
+                to_c_string(&Json::Array(moves).to_string())
+            }
+        }
+    })
+}
+
+/// Plays `mv` for the player whose turn it is.  Returns `QWIRKLER_OK` or one of the negative
+/// status codes, in which case the game is unchanged.
+///
+/// # Safety
+///
+/// `game` must be null or a handle from `qwirkler_game_new` that hasn't been freed, and no
+/// other call may use it at the same time.
+/// `mv` must be null or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn qwirkler_game_apply_move(game: *mut QwirklerGame, mv: *const c_char) -> c_int {
+    guard(QWIRKLER_INTERNAL_ERROR, || {
+        let game = match game.as_mut() {
+            None => return QWIRKLER_BAD_ARGUMENT,
+            Some(game) => game,
+        };
+        let text = match from_c_string(mv) {
+            None => {
+                game.last_error = "the move is null or not UTF-8".to_string();
+                return QWIRKLER_BAD_ARGUMENT;
+            }
+            Some(text) => text,
+        };
+        let proposed = match notation::parse_move(text) {
+            Err(reason) => {
+                game.last_error = reason;
+                return QWIRKLER_BAD_NOTATION;
+            }
+            Ok(proposed) => proposed,
+        };
+        if game.state.is_over() {
+            game.last_error = "the game is over".to_string();
+            return QWIRKLER_ILLEGAL_MOVE;
+        }
+        match game.state.validate_move(&proposed) {
+            Err(reason) => {
+                game.last_error = reason.to_string();
+                return QWIRKLER_ILLEGAL_MOVE;
+            }
+            Ok(legal) => {
+                game.state.apply_move(&legal);
+                return QWIRKLER_OK;
+            }
+        }
+    })
+}
+
+/// The move `strategy` would play for the player whose turn it is, in move notation.
+/// `strategy` is `greedy` or `search`; `movetime_ms` is only used by `search`.  Returns null if
+/// the game is over or the strategy is unknown.
+///
+/// # Safety
+///
+/// `game` must be null or a handle from `qwirkler_game_new` that hasn't been freed, and no
+/// other call may use it at the same time.
+/// `strategy` must be null or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn qwirkler_game_best_move(game: *mut QwirklerGame,
+                                                 strategy: *const c_char,
+                                                 movetime_ms: u32)
+                                                 -> *mut c_char {
+    guard(ptr::null_mut(), || {
+        let game = match game.as_mut() {
+            None => return ptr::null_mut(),
+            Some(game) => game,
+        };
+        if game.state.is_over() {
+            game.last_error = "the game is over".to_string();
+            return ptr::null_mut();
+        }
+        let chosen_move = match from_c_string(strategy) {
+            Some("greedy") => Greedy.choose_move(&game.state),
+            Some("search") => {
+                // seeded from the game so that the same calls give the same answers
+                let seed = (game.seed as usize).wrapping_add(game.state.history.len());
+                Search::new(Duration::from_millis(movetime_ms as u64), seed).choose_move(&game.state)
+            }
+            _ => {
+                game.last_error = "expected the strategy greedy or search".to_string();
+                return ptr::null_mut();
+            }
+        };
+        return to_c_string(&notation::format_move(&chosen_move));
+    })
+}
+
+/// Why the last call on `game` failed, or an empty string if nothing has failed yet.
+///
+/// # Safety
+///
+/// `game` must be null or a handle from `qwirkler_game_new` that hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn qwirkler_game_last_error(game: *const QwirklerGame) -> *mut c_char {
+    guard(ptr::null_mut(), || {
+        match game.as_ref() {
+            None => ptr::null_mut(),
+            Some(game) => to_c_string(&game.last_error),
+        }
+    })
+}
+
+#[test]
+fn ffi_should_play_a_game_through_handles() {
+    unsafe {
+        let game = qwirkler_game_new(2, ptr::null(), 7);
+        assert!(!game.is_null());
+        assert!(qwirkler_game_new(2, b"hand=99\0".as_ptr() as *const c_char, 7).is_null());
+
+        let bad = b"0,0 R 99\0";
+        assert!(qwirkler_game_apply_move(game, bad.as_ptr() as *const c_char) == QWIRKLER_BAD_NOTATION);
+        let far = b"30,30 R 11\0";
+        assert!(qwirkler_game_apply_move(game, far.as_ptr() as *const c_char) != QWIRKLER_OK);
+        let error = qwirkler_game_last_error(game);
+        assert!(CStr::from_ptr(error).to_bytes().len() > 0);
+        qwirkler_string_free(error);
+
+        while qwirkler_game_is_over(game) == 0 {
+            let chosen = qwirkler_game_best_move(game, b"greedy\0".as_ptr() as *const c_char, 0);
+            assert!(qwirkler_game_apply_move(game, chosen) == QWIRKLER_OK);
+            qwirkler_string_free(chosen);
+        }
+        let json = qwirkler_game_state_json(game, 0);
+        let state = Json::parse(CStr::from_ptr(json).to_str().unwrap()).unwrap();
+        assert!(state.get("over") == Some(&Json::Bool(true)));
+        qwirkler_string_free(json);
+        qwirkler_game_free(game);
+    }
+}
+
+#[test]
+fn guard_should_turn_panics_into_the_fallback() {
+    assert!(guard(QWIRKLER_INTERNAL_ERROR, || -> c_int { panic!("a bug") }) == QWIRKLER_INTERNAL_ERROR);
+    assert!(guard(QWIRKLER_INTERNAL_ERROR, || QWIRKLER_OK) == QWIRKLER_OK);
+    assert!(to_c_string("a\0b").is_null());
+}
+
+#[test]
+fn c_header_should_declare_every_function() {
+    let header = include_str!("../include/qwirkler.h");
+    for name in &["qwirkler_game_new",
+                  "qwirkler_game_free",
+                  "qwirkler_string_free",
+                  "qwirkler_game_turn",
+                  "qwirkler_game_is_over",
+                  "qwirkler_game_state_json",
+                  "qwirkler_game_position",
+                  "qwirkler_game_legal_moves",
+                  "qwirkler_game_apply_move",
+                  "qwirkler_game_best_move",
+                  "qwirkler_game_last_error"] {
+        assert!(header.contains(&format!("{}(", name)), "{} is missing from qwirkler.h", name);
+    }
+}
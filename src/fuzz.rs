@@ -0,0 +1,183 @@
+//! Fuzz targets for the rules engine.  Each target takes arbitrary bytes and must neither panic
+//! nor leave a game whose `check_invariants` finds anything wrong.
+//!
+//! `fuzz/` runs them under cargo-fuzz (`cargo fuzz run moves`), and the test at the bottom of
+//! this file runs them on pseudo-random input as part of `cargo test`.
+
+use direction::Direction;
+use gamestate::GameState;
+use mv::{Move, ScoreBreakdown};
+use notation;
+use piece::Piece;
+use rules::Rules;
+
+/// Hands out the input one byte at a time, then zeros once it runs out.
+struct Bytes<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Bytes<'a> {
+    fn next(&mut self) -> u8 {
+        let byte = self.data.get(self.position).cloned().unwrap_or(0);
+        self.position += 1;
+        return byte;
+    }
+
+    /// A coordinate, usually within a few squares of the middle of the board.  One time in
+    /// sixteen it is near `isize::MAX` or `isize::MIN` instead, or anywhere at all, since
+    /// stepping along a line from there would overflow.
+    fn coordinate(&mut self) -> isize {
+        let byte = self.next();
+        if byte % 16 != 0 {
+            return byte as i8 as isize / 4;
+        }
+        match byte / 16 % 3 {
+            0 => isize::MAX - self.next() as isize % 8,
+            1 => isize::MIN + self.next() as isize % 8,
+            _ => (0..8).fold(0, |acc: isize, _| acc.wrapping_shl(8) | self.next() as isize),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        return self.position >= self.data.len();
+    }
+}
+
+fn assert_consistent(game_state: &GameState) {
+    let violations = game_state.check_invariants();
+    assert!(violations.is_empty(), "{}", violations.join("\n"));
+}
+
+/// Plays a game decided by `data`.  The first three bytes choose the number of players and the
+/// deal.  After that, each move is a swap, one of the generated placements, or a placement
+/// made up from the bytes: a start square (which may be off the board), a direction and up to
+/// seven pieces (from the hand or not).  Every proposed move goes through `validate_move`, and
+/// legal ones are played.
+pub fn moves(data: &[u8]) {
+    let mut bytes = Bytes {
+        data: data,
+        position: 0,
+    };
+    let players = bytes.next() as usize % 4 + 1;
+    let seed = bytes.next() as usize * 256 + bytes.next() as usize;
+    let mut game_state = GameState::with_rules(players, Rules::standard(), seed);
+
+    while !bytes.is_empty() && !game_state.is_over() {
+        let proposed = match bytes.next() % 4 {
+            0 => Move::SwapPieces,
+            1 => {
+                let moves = game_state.generate_moves();
+                if moves.is_empty() {
+                    continue;
+                }
+                let generated = moves[bytes.next() as usize % moves.len()].clone();
+                // everything the generator finds must be legal, and scored the same way
+                let validated = game_state.validate_move(&generated);
+                assert!(validated.as_ref().map(|m| format!("{:?}", m)) == Ok(format!("{:?}", generated)),
+                        "{} was generated but is {:?}",
+                        notation::format_move(&generated),
+                        validated);
+                generated
+            }
+            _ => {
+                let square = (bytes.coordinate(), bytes.coordinate());
+                let direction = Direction::all()[bytes.next() as usize % 4];
+                let count = bytes.next() as usize % 8;
+                let hand = game_state.players[game_state.turn].bag.clone();
+                let pieces = (0..count)
+                                 .map(|_| {
+                                     let byte = bytes.next() as usize;
+                                     if byte < 128 && !hand.is_empty() {
+                                         hand[byte % hand.len()]
+                                     } else {
+                                         Piece::from_index(byte % 36)
+                                     }
+                                 })
+                                 .collect();
+                Move::PlacePieces(square, direction, pieces, ScoreBreakdown::default())
+            }
+        };
+        if let Ok(legal) = game_state.validate_move(&proposed) {
+            game_state.apply_move(&legal);
+            assert_consistent(&game_state);
+        }
+    }
+}
+
+/// Reads `data` as text with every notation parser.  Whatever parses must format back to text
+/// that parses the same way, and parsed positions and moves must be safe to play with.
+pub fn notation(data: &[u8]) {
+    let text = String::from_utf8_lossy(data);
+
+    if let Ok(piece) = notation::parse_piece(&text) {
+        assert!(notation::parse_piece(&piece.to_string()) == Ok(piece));
+    }
+    if let Ok(square) = notation::parse_square(&text) {
+        assert!(notation::parse_square(&format!("{},{}", square.0, square.1)) == Ok(square));
+    }
+    if let Ok(direction) = notation::parse_direction(&text) {
+        assert!(notation::parse_direction(notation::format_direction(&direction)) == Ok(direction));
+    }
+
+    if let Ok(proposed) = notation::parse_move(&text) {
+        let formatted = notation::format_move(&proposed);
+        let reparsed = notation::parse_move(&formatted).unwrap();
+        assert!(notation::format_move(&reparsed) == formatted);
+
+        let mut game_state = GameState::with_rules(2, Rules::standard(), 0);
+        // validate_move stops at pieces that aren't in the hand, so also score the placement
+        // against the board directly
+        if let Move::PlacePieces(sq, ref direction, ref pieces, _) = proposed {
+            let _ = game_state.board.score_placement(sq, direction, pieces);
+        }
+        if let Ok(legal) = game_state.validate_move(&proposed) {
+            game_state.apply_move(&legal);
+            assert_consistent(&game_state);
+        }
+    }
+
+    if let Ok((tiles, hand)) = notation::parse_position(&text) {
+        let formatted = notation::format_position(&tiles, &hand);
+        assert!(notation::parse_position(&formatted) == Ok((tiles.clone(), hand.clone())));
+
+        if let Ok(mut game_state) = GameState::from_position(&tiles, hand, Rules::standard(), 0) {
+            assert_consistent(&game_state);
+            if let Some(best) = game_state.generate_best_move() {
+                game_state.apply_move(&best);
+                assert_consistent(&game_state);
+            }
+        }
+    }
+}
+
+#[test]
+fn fuzz_targets_should_survive_random_input() {
+    use rand::{Rng, SeedableRng, StdRng};
+    let mut rng: StdRng = SeedableRng::from_seed(&[46][..]);
+    for _ in 0..200 {
+        let length = rng.gen_range(0, 300);
+        let data: Vec<u8> = (0..length).map(|_| rng.gen()).collect();
+        moves(&data);
+        notation(&data);
+    }
+
+    // random bytes rarely parse, so try text made from the characters notation uses
+    let alphabet = b"0123456789,,,==||  --RLUDswap";
+    for _ in 0..2000 {
+        let length = rng.gen_range(0, 40);
+        let text: Vec<u8> = (0..length).map(|_| alphabet[rng.gen_range(0, alphabet.len())]).collect();
+        notation(&text);
+    }
+    for text in &["0,0 R 11 12", "swap", "0,0=11 1,0=12 | 13 21", "23,0=11 | 12", "-24,0 L 11",
+                  "99999999999999999999,0 R 11", "0,0=11 1,0=11 |", "0,0=11 0,0=12 | 13",
+                  // these used to overflow working out the squares of the placement
+                  "9223372036854775807,0 R 11", "-9223372036854775808,0 U 11",
+                  "0,-9223372036854775808 D 11 12", "-9223372036854775808,5 L 11"] {
+        notation(text.as_bytes());
+    }
+    // and through the moves target: one player, seed 0, then a made-up placement of two
+    // pieces from the hand, from isize::MAX,0 going right and from isize::MIN,0 going left
+    moves(&[0, 0, 0, 2, 0x00, 0, 1, 3, 2, 0, 1]);
+    moves(&[0, 0, 0, 2, 0x10, 0, 1, 2, 2, 0, 1]);
+}
@@ -1,27 +1,163 @@
 use board::Board;
-use mv::Move;
+use dead::DeadSpots;
+use linevalidator::LineValidator;
+use direction::{Square, Direction};
+use mv::{Move, IllegalMove, ScoreBreakdown};
 use partial::Partial;
-use piece::Bag;
+use piece::{Bag, Piece};
 use piece;
 use player::{PlayerState, Score};
-use std::collections::VecDeque;
+use rand::{thread_rng, Rng, SeedableRng, StdRng};
+use rules::Rules;
+use view::PlayerView;
+use zobrist;
+use std::cmp::Ordering;
 use std::mem;
 
+/// One of the moves suggested by `GameState::hints`.
+#[derive(Debug, Clone)]
+pub struct Hint {
+    pub chosen_move: Move,
+}
+
+impl Hint {
+    /// How the move scores, line by line (empty for a swap, which `hints` never suggests).
+    pub fn breakdown(&self) -> ScoreBreakdown {
+        match self.chosen_move {
+            Move::PlacePieces(_, _, _, ref breakdown) => breakdown.clone(),
+            Move::SwapPieces => ScoreBreakdown::default(),
+        }
+    }
+}
+
+/// How `GameState::choose_best_move` picks between placements that share the highest score.
+/// The tied placements are put in `candidate_order` first, so the choice never depends on the
+/// order the generator found them in, and a game with a fixed seed replays exactly.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TieBreak {
+    /// The first placement.
+    First,
+    /// A placement drawn at random, seeded by the given seed and the number of moves played.
+    Random(usize),
+    /// The placement with the most pieces.
+    PreferLonger,
+    /// The placement that leaves the most duplicate pieces in the hand.
+    PreferKeepingDuplicates,
+}
+
+pub const TIE_BREAKS: &'static [&'static str] = &["first", "random", "longer", "duplicates"];
+
+impl TieBreak {
+    /// Looks up a policy by the name in `TIE_BREAKS`.  `random` draws from `seed`.
+    pub fn parse(name: &str, seed: usize) -> Result<TieBreak, String> {
+        match name {
+            "first" => Ok(TieBreak::First),
+            "random" => Ok(TieBreak::Random(seed)),
+            "longer" => Ok(TieBreak::PreferLonger),
+            "duplicates" => Ok(TieBreak::PreferKeepingDuplicates),
+            _ => {
+                Err(format!("unknown tie-break '{}', expected one of: {}",
+                            name,
+                            TIE_BREAKS.join(", ")))
+            }
+        }
+    }
+
+    /// Picks one of `candidates` (which must be in `candidate_order`), for a player holding
+    /// `hand` when `moves_played` moves have been played.  Among candidates the policy can't
+    /// tell apart, the first wins.
+    fn choose(&self, mut candidates: Vec<Move>, hand: &[Piece], moves_played: usize) -> Move {
+        let i = match *self {
+            TieBreak::First => 0,
+            TieBreak::Random(seed) => {
+                let mut rng: StdRng = SeedableRng::from_seed(&[seed, moves_played][..]);
+                rng.gen_range(0, candidates.len())
+            }
+            TieBreak::PreferLonger => first_max_by_key(&candidates, |m| m.squares().len()),
+            TieBreak::PreferKeepingDuplicates => {
+                first_max_by_key(&candidates, |m| duplicates_left(hand, m))
+            }
+        };
+        return candidates.swap_remove(i);
+    }
+}
+
+/// The index of the first of `moves` with the largest `key`.
+fn first_max_by_key<F: Fn(&Move) -> usize>(moves: &[Move], key: F) -> usize {
+    let mut best = 0;
+    for i in 1..moves.len() {
+        if key(&moves[i]) > key(&moves[best]) {
+            best = i;
+        }
+    }
+    return best;
+}
+
+/// How many pieces left in `hand` after playing `chosen_move` are copies of another piece
+/// left in it.
+fn duplicates_left(hand: &[Piece], chosen_move: &Move) -> usize {
+    let mut left = hand.to_vec();
+    if let Move::PlacePieces(_, _, ref pieces, _) = *chosen_move {
+        for placed in pieces {
+            if let Some(i) = left.iter().position(|piece| piece == placed) {
+                left.remove(i);
+            }
+        }
+    }
+    return (0..left.len()).filter(|&i| left[..i].contains(&left[i])).count();
+}
+
+/// The order candidate moves are considered in: by the squares and pieces they cover, each
+/// placement listed by row, then column, then piece.  Two ways of writing down the same
+/// placement compare equal, and swapping comes before any placement.
+pub fn candidate_order(a: &Move, b: &Move) -> Ordering {
+    return candidate_key(a).cmp(&candidate_key(b));
+}
+
+/// What `candidate_order` compares.  Building it allocates, so sorts work it out once per
+/// move (`sort_by_cached_key`) rather than once per comparison.
+type CandidateKey = Vec<(isize, isize, usize)>;
+
+fn candidate_key(m: &Move) -> CandidateKey {
+    let mut placed: CandidateKey = match *m {
+        Move::SwapPieces => vec![],
+        Move::PlacePieces(_, _, ref pieces, _) => {
+            m.squares().into_iter().zip(pieces).map(|((x, y), p)| (y, x, p.index())).collect()
+        }
+    };
+    placed.sort();
+    return placed;
+}
+
 pub struct GameState {
     pub board: Board,
     pub players: Vec<PlayerState>,
     bag: Bag,
     pub turn: usize,
+    pub rules: Rules,
+    /// Every move applied so far, in order.  The player who made move `i` is
+    /// `i % players.len()`.
+    pub history: Vec<Move>,
+    rng: StdRng,
 }
 
 
 impl GameState {
     pub fn new(num_players: isize) -> GameState {
-        let mut initial_bag = piece::make_bag();
+        return GameState::with_rules(num_players as usize, Rules::standard(), thread_rng().gen());
+    }
+
+    /// Creates a game whose draws from the bag are entirely determined by `seed`.
+    pub fn with_rules(num_players: usize, rules: Rules, seed: usize) -> GameState {
+        let mut rng: StdRng = SeedableRng::from_seed(&[seed][..]);
+        let mut initial_bag = piece::make_bag_with_copies(rules.copies);
         let players = (0..num_players)
                           .map(|_| {
                               let mut ps = PlayerState::new();
-                              piece::resupply_player_mutate(&mut ps.bag, &mut initial_bag);
+                              piece::resupply_player(&mut ps.bag,
+                                                     &mut initial_bag,
+                                                     rules.hand_size,
+                                                     &mut rng);
                               ps
                           })
                           .collect();
@@ -30,84 +166,679 @@ impl GameState {
             players: players,
             bag: initial_bag,
             turn: 0,
+            rules: rules,
+            history: vec![],
+            rng: rng,
         };
     }
 
+    /// Creates a one player game from a board position and that player's hand.
+    /// The bag holds every remaining piece, so it is an error to use more copies of a piece than
+    /// the rules allow.  It is also an error for any line on the board to be invalid.
+    pub fn from_position(tiles: &[(Square, Piece)],
+                         hand: Bag,
+                         rules: Rules,
+                         seed: usize)
+                         -> Result<GameState, String> {
+        let mut bag = piece::make_bag_with_copies(rules.copies);
+        for piece in tiles.iter().map(|&(_, p)| p).chain(hand.iter().cloned()) {
+            match bag.iter().position(|&p| p == piece) {
+                None => return Err(format!("too many copies of {}", piece.to_string())),
+                Some(i) => {
+                    bag.swap_remove(i);
+                }
+            }
+        }
+        let mut board = Board::new();
+        for &(sq, piece) in tiles {
+            if !board.in_bounds(sq) {
+                return Err(format!("{},{} is off the edge of the board", sq.0, sq.1));
+            }
+            if board.get(sq).is_some() {
+                return Err(format!("{},{} has two pieces on it", sq.0, sq.1));
+            }
+            board.put(sq, &Direction::initial(), &vec![piece]);
+        }
+        if let Some(violation) = board.check_invariants().into_iter().next() {
+            return Err(violation);
+        }
+        let mut player = PlayerState::new();
+        player.bag = hand;
+        return Ok(GameState {
+            board: board,
+            players: vec![player],
+            bag: bag,
+            turn: 0,
+            rules: rules,
+            history: vec![],
+            rng: SeedableRng::from_seed(&[seed][..]),
+        });
+    }
+
     pub fn total_score(&self) -> Score {
         return self.players.iter().fold(0, |acc, p| acc + p.score);
     }
 
-    // TODO re-use code between generate_best_move and generate_moves
-    pub fn generate_best_move(&self) -> Option<Move> {
-        let mut best_score = 0;
-        let mut best_move = Move::SwapPieces;
-
-        // We use a VecDeque as a queue to test increasingly long sequences of pieces
-        // without repeating any validation or scoring work we did testing the prefixes.
-        let mut queue: VecDeque<Partial> = VecDeque::new();
-
-        // Invariants for every partial in the queue:
-        //  * every prefix of partial.pieces has already been validated (so we just need to check the last piece)
-        //  * we have already computed the score for the n-1 prefix
-        //  * partial.last_square is the square that the last piece would fall on
-        //  * partial.main_validator is the result of validating everything before the start of the line,
-        //    and everything except the last element in the `pieces` vector (ie, it's None for singletons)
-        //  * partial.perp_scores stores the poisizes that would be gained from any perpendicular lines that
-        //    this play would form.
-
-        // figure out possible start squares (and directions).
-        for &(square, ref direction) in &self.board.get_start_squares() {
-            // initialize queue with singletons
-            for &piece in &self.players[self.turn].bag {
-                queue.push_back(Partial::new(square, direction, piece));
-            }
-            // figure out any possible moves starting at this start square and direction, add to `moves`
-            loop {
-                match queue.pop_front().as_mut() {
-                    None => break,
-                    Some(partial) => {
-                        if self.board.allows(partial) {
-                            // put new partials back in
-                            for &p in &self.players[self.turn].bag {
-                                match partial.try_extend(p) {
-                                    None => {}
-                                    Some(extended) => queue.push_back(extended),
-                                }
-                            }
-
-                            // calculate full score and return move
-                            if partial.total_score() > best_score {
-                                best_score = partial.total_score();
-                                best_move = partial.save_as_move();
-                            }
+    /// A hash of the whole position: the board, every hand, the bag and whose turn it is.
+    /// The board's part is kept up to date as moves are made; the rest is worked out here.
+    /// Scores and history aren't included.
+    pub fn zobrist(&self) -> u64 {
+        let mut hash = self.board.zobrist() ^ zobrist::holding_hash(zobrist::BAG, &self.bag) ^
+                       zobrist::turn_key(self.turn);
+        for (i, player) in self.players.iter().enumerate() {
+            hash ^= zobrist::holding_hash(i, &player.bag);
+        }
+        return hash;
+    }
+
+    /// Checks that the game is consistent: the board (see `Board::check_invariants`), that
+    /// the board, hands and bag hold exactly the pieces the game started with, and that every
+    /// player's score is what their moves score when played again.  Returns a description of
+    /// every problem found.
+    pub fn check_invariants(&self) -> Vec<String> {
+        let mut violations = self.board.check_invariants();
+
+        let mut counts = [0; 36];
+        let on_board = self.board.tiles().into_iter().map(|(_, piece)| piece);
+        let in_hands = self.players.iter().flat_map(|p| p.bag.iter().cloned());
+        for piece in on_board.chain(in_hands).chain(self.bag.iter().cloned()) {
+            counts[piece.index()] += 1;
+        }
+        for (i, &count) in counts.iter().enumerate() {
+            if count != self.rules.copies {
+                let piece = Piece::from_index(i);
+                violations.push(format!("there are {} copies of {}, not {}",
+                                        count,
+                                        piece.to_string(),
+                                        self.rules.copies));
+            }
+        }
+        for (i, player) in self.players.iter().enumerate() {
+            if player.bag.len() > self.rules.hand_size {
+                violations.push(format!("player {} holds {} pieces", i + 1, player.bag.len()));
+            }
+        }
+
+        // take the moves back to find the starting board, then play them again
+        let mut board = self.board.clone();
+        for chosen_move in self.history.iter().rev() {
+            if let Move::PlacePieces(sq, ref direction, ref pieces, _) = *chosen_move {
+                board.undo(sq, direction, pieces.len());
+            }
+        }
+        let mut scores = vec![0; self.players.len()];
+        for (i, chosen_move) in self.history.iter().enumerate() {
+            if let Move::PlacePieces(sq, ref direction, ref pieces, ref breakdown) = *chosen_move {
+                match board.score_placement(sq, direction, pieces) {
+                    Ok(ref rescored) if rescored == breakdown => {}
+                    Ok(rescored) => {
+                        violations.push(format!("move {} scored {} but should have scored {}",
+                                                i + 1,
+                                                breakdown,
+                                                rescored))
+                    }
+                    Err(reason) => violations.push(format!("move {} was illegal: {}", i + 1, reason)),
+                }
+                board.put(sq, direction, pieces);
+                scores[i % self.players.len()] += breakdown.total();
+            }
+        }
+        for (i, player) in self.players.iter().enumerate() {
+            if player.score != scores[i] {
+                violations.push(format!("player {} has {} points but their moves scored {}",
+                                        i + 1,
+                                        player.score,
+                                        scores[i]));
+            }
+        }
+        return violations;
+    }
+
+    /// The squares that can never be filled and lines that can never be completed, given how
+    /// many copies of each piece this game has.  None of the built-in strategies consult it
+    /// yet; it is for ones that want to avoid playing for a Qwirkle that can't happen.
+    pub fn dead_spots(&self) -> DeadSpots {
+        return DeadSpots::find(&self.board, self.rules.copies);
+    }
+
+    pub fn bag_size(&self) -> usize {
+        return self.bag.len();
+    }
+
+    /// What `player` can see of the game.
+    pub fn view(&self, player: usize) -> PlayerView {
+        return PlayerView {
+            player: player,
+            turn: self.turn,
+            over: self.is_over(),
+            rules: self.rules,
+            tiles: self.board.tiles(),
+            hand: self.players[player].bag.clone(),
+            scores: self.players.iter().map(|p| p.score).collect(),
+            bag_size: self.bag.len(),
+        };
+    }
+
+    /// Checks that `proposed` is legal for the player whose turn it is, and returns the same move
+    /// with its score filled in.  Any score already present in `proposed` is ignored.
+    pub fn validate_move(&self, proposed: &Move) -> Result<Move, IllegalMove> {
+        match proposed {
+            &Move::SwapPieces => {
+                if self.bag.len() == 0 {
+                    return Err(IllegalMove::BagEmpty);
+                }
+                return Ok(Move::SwapPieces);
+            }
+            &Move::PlacePieces(sq, ref dir, ref pieces, _) => {
+                let mut hand = self.players[self.turn].bag.clone();
+                for piece in pieces {
+                    match hand.iter().position(|p| p == piece) {
+                        None => return Err(IllegalMove::NotInHand(*piece)),
+                        Some(i) => {
+                            hand.swap_remove(i);
                         }
                     }
                 }
+                let breakdown = self.board.score_placement(sq, dir, pieces)?;
+                return Ok(Move::PlacePieces(sq, *dir, pieces.clone(), breakdown));
             }
         }
-        if self.bag.len() == 0 && best_score == 0 {
+    }
+
+    /// Returns true once the bag is empty and the current player has no pieces they can place.
+    pub fn is_over(&self) -> bool {
+        // while the bag has pieces there is always a swap
+        return self.bag.len() == 0 && !self.can_place();
+    }
+
+    /// True if the current player has a piece they can place.  Every placement has a piece on
+    /// the perimeter that could also be placed on its own, so single pieces are enough to check.
+    fn can_place(&self) -> bool {
+        let hand_mask = self.players[self.turn].bag.iter().fold(0u64, |mask, piece| mask | 1 << piece.index());
+        if self.board.perimeter().is_empty() {
+            return hand_mask != 0;
+        }
+        return self.board.perimeter().iter().any(|&sq| {
+            self.board.cross_check(sq, &Direction::R).allowed &
+            self.board.cross_check(sq, &Direction::U).allowed & hand_mask != 0
+        });
+    }
+
+    /// The highest scoring placement, the first in `candidate_order` if several tie, or a swap
+    /// if nothing can be placed.  `None` once the game is over.
+    pub fn generate_best_move(&self) -> Option<Move> {
+        return self.choose_best_move(TieBreak::First);
+    }
+
+    /// Like `generate_best_move`, with `tie_break` choosing between equally scoring placements.
+    pub fn choose_best_move(&self, tie_break: TieBreak) -> Option<Move> {
+        let hand = &self.players[self.turn].bag;
+        let candidates = best_placements(&self.board, hand);
+        if !candidates.is_empty() {
+            return Some(tie_break.choose(candidates, hand, self.history.len()));
+        }
+        if self.bag.len() == 0 {
             return None;
-        } else {
-            return Some(best_move);
         }
+        return Some(Move::SwapPieces);
+    }
+
+    /// Lists every legal placement for the current player, in `candidate_order`.  Swapping is
+    /// not included.
+    pub fn generate_moves(&self) -> Vec<Move> {
+        let mut moves = vec![];
+        visit_partials(&self.board,
+                       &self.players[self.turn].bag,
+                       |partial| moves.push(partial.save_as_move()));
+        moves.sort_by_cached_key(candidate_key);
+        return moves;
+    }
+
+    /// The `n` highest scoring placements for the current player, best first, each with a
+    /// breakdown of its score.  Placements with equal scores are in `candidate_order`.
+    /// Swapping is never suggested.
+    pub fn hints(&self, n: usize) -> Vec<Hint> {
+        // each kept move with its candidate_key, so ties compare without rebuilding keys
+        let mut best: Vec<(Move, CandidateKey)> = vec![];
+        visit_partials(&self.board, &self.players[self.turn].bag, |partial| {
+            let score = partial.total_score();
+            if best.len() == n && (n == 0 || score < best[n - 1].0.score()) {
+                return;
+            }
+            let found = partial.save_as_move();
+            let key = candidate_key(&found);
+            let i = best.iter()
+                        .position(|&(ref m, ref other)| {
+                            m.score() < score || (m.score() == score && key < *other)
+                        })
+                        .unwrap_or(best.len());
+            best.insert(i, (found, key));
+            best.truncate(n);
+        });
+        return best.into_iter().map(|(chosen_move, _)| Hint { chosen_move: chosen_move }).collect();
+    }
+
+    /// Pieces that `player` hasn't seen: the bag and every other player's hand.
+    pub fn unseen_pieces(&self, player: usize) -> Bag {
+        let mut unseen = self.bag.clone();
+        for (i, other) in self.players.iter().enumerate() {
+            if i != player {
+                unseen.extend_from_slice(&other.bag);
+            }
+        }
+        return unseen;
     }
 
     pub fn apply_move(&mut self, chosen_move: &Move) {
         match chosen_move {
-            &Move::PlacePieces(sq, ref dir, ref pieces_to_place, score) => {
+            &Move::PlacePieces(sq, ref dir, ref pieces_to_place, ref breakdown) => {
                 self.board.put(sq, dir, pieces_to_place);
-                self.players[self.turn].bag.retain(|&piece| {
-                    pieces_to_place.iter().all(|&p_to_remove| piece != p_to_remove)
-                });
-                piece::resupply_player_mutate(&mut self.players[self.turn].bag, &mut self.bag);
-                self.players[self.turn].score += score;
+                // only one copy of each placed piece leaves the hand
+                for placed in pieces_to_place {
+                    let hand = &mut self.players[self.turn].bag;
+                    if let Some(i) = hand.iter().position(|piece| piece == placed) {
+                        hand.remove(i);
+                    }
+                }
+                piece::resupply_player(&mut self.players[self.turn].bag,
+                                       &mut self.bag,
+                                       self.rules.hand_size,
+                                       &mut self.rng);
+                self.players[self.turn].score += breakdown.total();
             }
             &Move::SwapPieces => {
                 let oldbag = mem::replace(&mut self.players[self.turn].bag, vec![]);
                 self.bag.extend_from_slice(oldbag.as_slice());
-                piece::resupply_player_mutate(&mut self.players[self.turn].bag, &mut self.bag);
+                piece::resupply_player(&mut self.players[self.turn].bag,
+                                       &mut self.bag,
+                                       self.rules.hand_size,
+                                       &mut self.rng);
             }
         }
+        self.history.push(chosen_move.clone());
         self.turn = (self.turn + 1) % self.players.len();
     }
 }
+
+/// The highest scoring placement of pieces from `hand`, the first in `candidate_order` if
+/// several tie, or `None` if nothing can be placed.
+pub fn best_placement(board: &Board, hand: &[Piece]) -> Option<Move> {
+    return best_placements(board, hand).into_iter().next();
+}
+
+/// Every placement sharing the highest score, in `candidate_order`.
+fn best_placements(board: &Board, hand: &[Piece]) -> Vec<Move> {
+    // partials are plain values, so ties are kept without building a Move for each; only the
+    // list of them grows
+    let mut best_score = 0;
+    let mut best_partials: Vec<Partial> = vec![];
+    visit_partials(board, hand, |partial| {
+        let score = partial.total_score();
+        if score > best_score {
+            best_score = score;
+            best_partials.clear();
+        }
+        if score == best_score {
+            best_partials.push(*partial);
+        }
+    });
+    let mut best_moves: Vec<Move> = best_partials.iter().map(|p| p.save_as_move()).collect();
+    best_moves.sort_by_cached_key(candidate_key);
+    return best_moves;
+}
+
+/// Counts the work done by `visit_partials`, and the work it avoided.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct GenerationStats {
+    /// Partial plays checked against the board.
+    pub partials: usize,
+    /// Placements passed to `visit`.
+    pub placements: usize,
+    /// Extensions not tried because an identical piece from the hand had already been tried.
+    pub repeated_pieces: usize,
+    /// Start squares not searched leftwards or downwards, since every placement found that way
+    /// is also found going right or up from its other end.
+    pub mirrored_starts: usize,
+}
+
+/// Calls `visit` with every legal partial play (ie every placement, fully scored) of pieces
+/// from `hand`.  Each distinct placement is visited once.
+pub fn visit_partials<F: FnMut(&Partial)>(board: &Board, hand: &[Piece], visit: F) -> GenerationStats {
+    // Identical pieces can never share a line, so a hand with duplicates only needs each kind
+    // of piece trying once.
+    let mut pieces: Vec<Piece> = vec![];
+    for &piece in hand {
+        if !pieces.contains(&piece) {
+            pieces.push(piece);
+        }
+    }
+    let repeats = hand.len() - pieces.len();
+    let hand_mask = pieces.iter().fold(0u64, |mask, piece| mask | 1 << piece.index());
+
+    let mut generator = Generator {
+        board: board,
+        pieces: &pieces,
+        hand_mask: hand_mask,
+        repeats: repeats,
+        stats: GenerationStats::default(),
+        visit: visit,
+    };
+
+    // figure out possible start squares (and directions).
+    for &(square, direction, reach) in &board.start_squares() {
+        if reach == 1 {
+            generator.stats.mirrored_starts += 1;
+        }
+        // the pieces that could land on the perimeter square this start reaches
+        let perimeter_square = (1..reach).fold(square, |sq, _| direction.apply(sq));
+        let anchor = Anchor {
+            reach: reach,
+            allowed: board.cross_check(perimeter_square, &direction).allowed & hand_mask,
+        };
+        // start with singletons, skipping any that the cross-check rules out
+        let allowed = board.cross_check(square, &direction).allowed;
+        for &piece in &pieces {
+            if allowed & (1 << piece.index()) != 0 &&
+               generator.can_reach(&anchor, LineValidator::new(piece).allowed(), 1) {
+                generator.search(Partial::new(square, &direction, piece), &anchor);
+            }
+        }
+        generator.stats.repeated_pieces += repeats;
+    }
+    return generator.stats;
+}
+
+/// How far a start square is from the perimeter, and which pieces would fit on the perimeter
+/// square it reaches.
+struct Anchor {
+    reach: usize,
+    allowed: u64,
+}
+
+/// The state of one `visit_partials` call.  The search is depth first, and partials are plain
+/// values, so the only memory it needs is the stack (at most six frames deep).
+struct Generator<'a, F> {
+    board: &'a Board,
+    pieces: &'a [Piece],
+    hand_mask: u64,
+    repeats: usize,
+    stats: GenerationStats,
+    visit: F,
+}
+
+impl<'a, F: FnMut(&Partial)> Generator<'a, F> {
+    /// A line that still has to reach the perimeter needs enough pieces left to get there, and
+    /// one of them has to fit on the perimeter square.
+    fn can_reach(&self, anchor: &Anchor, line_allowed: u64, length: usize) -> bool {
+        return length >= anchor.reach ||
+               (length + ((line_allowed & self.hand_mask).count_ones() as usize) >= anchor.reach &&
+                line_allowed & anchor.allowed != 0);
+    }
+
+    /// Checks the last piece of `partial`, visits it if it is a placement, then tries every
+    /// extension of it.
+    ///
+    /// Every prefix of `partial` has already been checked, and its scores and `main_validator`
+    /// cover everything except the last piece (ie `main_validator` is None for singletons).
+    fn search(&mut self, mut partial: Partial, anchor: &Anchor) {
+        self.stats.partials += 1;
+        if !self.board.allows(&mut partial) {
+            return;
+        }
+        // give up on lines that can't reach the perimeter with the pieces left
+        let length = partial.pieces().len();
+        if !self.can_reach(anchor, partial.main_validator.map_or(0, |lv| lv.allowed()), length) {
+            return;
+        }
+
+        // Only placements that reach the perimeter are connected, and a single piece is the
+        // same placement whichever way its line runs.
+        if length >= anchor.reach && !(length == 1 && partial.direction == Direction::U) {
+            self.stats.placements += 1;
+            (self.visit)(&partial);
+        }
+
+        let allowed = self.board
+                          .cross_check(partial.direction.apply(partial.last_square), &partial.direction)
+                          .allowed;
+        let pieces = self.pieces;
+        for &p in pieces {
+            if allowed & (1 << p.index()) == 0 {
+                continue;
+            }
+            if let Some(extended) = partial.try_extend(p) {
+                self.search(extended, anchor);
+            }
+        }
+        self.stats.repeated_pieces += self.repeats;
+    }
+}
+
+#[test]
+fn games_with_the_same_seed_should_draw_the_same_pieces() {
+    let draws = |seed| {
+        let mut game_state = GameState::with_rules(3, Rules::standard(), seed);
+        game_state.apply_move(&Move::SwapPieces);
+        game_state.players.iter().map(|p| p.bag.clone()).collect::<Vec<Bag>>()
+    };
+    assert!(draws(42) == draws(42));
+    assert!(draws(42) != draws(43));
+}
+
+#[test]
+fn from_position_should_reject_too_many_copies() {
+    let p = Piece::new(piece::Colour::R, piece::Shape::A);
+    let tiles = vec![((0, 0), p), ((2, 0), p)];
+    assert!(GameState::from_position(&tiles, vec![p, p], Rules::standard(), 0).is_err());
+    assert!(GameState::from_position(&tiles, vec![p], Rules::standard(), 0).is_ok());
+    // and two of the same piece can't share a line
+    assert!(GameState::from_position(&[((0, 0), p), ((1, 0), p)], vec![], Rules::standard(), 0).is_err());
+}
+
+#[test]
+fn validate_move_should_reject_huge_coordinates() {
+    use notation;
+    let (tiles, hand) = notation::parse_position("0,0=11 | 12 13").unwrap();
+    let game_state = GameState::from_position(&tiles, hand, Rules::standard(), 0).unwrap();
+    for text in &["9223372036854775807,0 R 12 13", "-9223372036854775808,0 L 12 13",
+                  "0,9223372036854775807 U 12", "0,-9223372036854775808 D 12"] {
+        match game_state.validate_move(&notation::parse_move(text).unwrap()) {
+            Err(IllegalMove::OutOfBounds(_)) => {}
+            other => panic!("expected {} to be out of bounds, got {:?}", text, other),
+        }
+    }
+}
+
+#[test]
+fn hints_should_list_the_best_moves_with_breakdowns_that_add_up() {
+    use reference;
+    let mut game_state = GameState::with_rules(2, Rules::standard(), 9);
+    for _ in 0..6 {
+        let chosen_move = game_state.generate_best_move().unwrap();
+        game_state.apply_move(&chosen_move);
+    }
+    let hints = game_state.hints(5);
+    assert!(hints.len() == 5);
+    assert!(hints[0].chosen_move.score() ==
+            game_state.generate_best_move().unwrap().score());
+    for pair in hints.windows(2) {
+        assert!(pair[0].chosen_move.score() >= pair[1].chosen_move.score());
+    }
+    for hint in &hints {
+        assert!(hint.breakdown().total() == hint.chosen_move.score());
+        // the generator's breakdown agrees with the reference scorer's
+        if let Move::PlacePieces(sq, dir, ref pieces, _) = hint.chosen_move {
+            let expected = reference::breakdown(&game_state.board, sq, dir, pieces);
+            assert!(expected == Some(hint.breakdown()));
+        }
+    }
+    assert!(game_state.hints(0).len() == 0);
+}
+
+#[test]
+fn generate_moves_should_find_each_placement_once() {
+    use notation;
+    let (tiles, hand) = notation::parse_position("0,1=11 | 22 21 23 21 22 35").unwrap();
+    let game_state = GameState::from_position(&tiles, hand.clone(), Rules::standard(), 0).unwrap();
+    let moves = game_state.generate_moves();
+
+    // the same squares and pieces, however the move was written down
+    let mut placements: Vec<Vec<(Square, usize)>> = moves.iter()
+                                                          .map(|m| {
+                                                              let mut placed = match *m {
+                                                                  Move::PlacePieces(_, _, ref pieces, _) => {
+                                                                      m.squares()
+                                                                       .into_iter()
+                                                                       .zip(pieces.iter().map(|p| p.index()))
+                                                                       .collect::<Vec<_>>()
+                                                                  }
+                                                                  Move::SwapPieces => vec![],
+                                                              };
+                                                              placed.sort();
+                                                              placed
+                                                          })
+                                                          .collect();
+    let count = placements.len();
+    placements.sort();
+    placements.dedup();
+    assert!(placements.len() == count);
+
+    // the line crosses the perimeter in its middle, so neither end touches a tile
+    assert!(moves.iter().any(|m| notation::format_move(m) == "-1,0 R 22 21 23"));
+    assert!(game_state.validate_move(&notation::parse_move("-1,0 R 22 21 23").unwrap()).ok()
+                      .map(|m| m.score()) == Some(5));
+
+    let stats = visit_partials(&game_state.board, &hand, |_| {});
+    assert!(stats.placements == count);
+    assert!(stats.repeated_pieces > 0 && stats.mirrored_starts > 0);
+}
+
+#[test]
+fn zobrist_should_tell_self_play_positions_apart() {
+    use std::collections::HashMap;
+    let mut seen: HashMap<u64, String> = HashMap::new();
+    let mut positions = 0;
+    for seed in 0..20 {
+        let mut game_state = GameState::with_rules(2, Rules::standard(), seed);
+        while !game_state.is_over() {
+            let sorted = |pieces: &[Piece]| {
+                let mut indices: Vec<usize> = pieces.iter().map(|p| p.index()).collect();
+                indices.sort();
+                indices
+            };
+            let hands: Vec<Vec<usize>> = game_state.players.iter().map(|p| sorted(&p.bag)).collect();
+            let position = format!("{:?} {:?} {:?} {}",
+                                   game_state.board.tiles(),
+                                   hands,
+                                   sorted(&game_state.bag),
+                                   game_state.turn);
+            let previous = seen.entry(game_state.zobrist()).or_insert(position.clone());
+            assert!(*previous == position, "{} and {} have the same hash", previous, position);
+            positions += 1;
+
+            let chosen_move = game_state.generate_best_move().unwrap();
+            game_state.apply_move(&chosen_move);
+        }
+    }
+    assert!(positions > 500 && seen.len() > 500);
+}
+
+#[test]
+fn is_over_should_agree_with_the_best_move() {
+    for players in 1..5 {
+        let mut game_state = GameState::with_rules(players, Rules::standard(), players);
+        loop {
+            let best = game_state.generate_best_move();
+            assert!(game_state.is_over() == best.is_none());
+            match best {
+                Some(chosen_move) => game_state.apply_move(&chosen_move),
+                None => break,
+            }
+        }
+        assert!(game_state.bag.len() == 0);
+    }
+}
+
+#[test]
+fn apply_move_should_only_take_the_pieces_played_from_the_hand() {
+    use notation;
+    let (tiles, hand) = notation::parse_position("| 11 11 12").unwrap();
+    let mut game_state = GameState::from_position(&tiles, hand, Rules::standard(), 0).unwrap();
+    let chosen_move = game_state.validate_move(&notation::parse_move("0,0 R 11 12").unwrap()).unwrap();
+    game_state.apply_move(&chosen_move);
+    assert!(game_state.players[0].bag.iter().filter(|p| p.to_string() == "11").count() == 1);
+    assert!(game_state.players[0].bag.len() == 6);
+}
+
+#[test]
+fn check_invariants_should_report_inconsistencies() {
+    let mut game_state = GameState::with_rules(2, Rules::standard(), 9);
+    for _ in 0..6 {
+        let chosen_move = game_state.generate_best_move().unwrap();
+        game_state.apply_move(&chosen_move);
+    }
+    assert!(game_state.check_invariants().is_empty());
+
+    game_state.players[1].score += 1;
+    let lost = game_state.bag.pop().unwrap();
+    let violations = game_state.check_invariants();
+    assert!(violations.len() == 2);
+    assert!(violations.contains(&format!("there are 2 copies of {}, not 3", lost.to_string())));
+    assert!(violations.iter().any(|v| v.starts_with("player 2 has ")));
+}
+
+#[test]
+fn tie_breaks_should_pick_between_candidates_by_policy() {
+    use notation;
+    let parse = |text| notation::parse_move(text).unwrap();
+    let (_, hand) = notation::parse_position("| 11 11 12 13 14").unwrap();
+    let mut candidates = vec![parse("0,1 U 12 13"), parse("2,0 R 12"), parse("0,0 R 11")];
+    candidates.sort_by(candidate_order);
+    let chosen = |tie_break: TieBreak, moves_played| {
+        notation::format_move(&tie_break.choose(candidates.clone(), &hand, moves_played))
+    };
+    assert!(chosen(TieBreak::First, 0) == "0,0 R 11");
+    assert!(chosen(TieBreak::PreferLonger, 0) == "0,1 U 12 13");
+    // playing 11 would leave no duplicates, so the first of the other two wins
+    assert!(chosen(TieBreak::PreferKeepingDuplicates, 0) == "2,0 R 12");
+    assert!(chosen(TieBreak::Random(3), 5) == chosen(TieBreak::Random(3), 5));
+    let drawn: Vec<String> = (0..20).map(|n| chosen(TieBreak::Random(3), n)).collect();
+    assert!(drawn.iter().any(|m| *m != drawn[0]));
+
+    // the same placement written from its other end is the same candidate
+    assert!(candidate_order(&parse("0,1 U 12 13"), &parse("0,2 D 13 12")) == Ordering::Equal);
+    assert!(candidate_order(&Move::SwapPieces, &parse("0,0 R 11")) == Ordering::Less);
+    assert!(TieBreak::parse("longer", 0) == Ok(TieBreak::PreferLonger));
+    assert!(TieBreak::parse("last", 0).is_err());
+}
+
+#[test]
+fn games_should_replay_exactly_under_every_tie_break() {
+    use notation;
+    let play = |tie_break: TieBreak| {
+        let mut game_state = GameState::with_rules(2, Rules::standard(), 47);
+        let mut moves = vec![];
+        for _ in 0..12 {
+            let chosen_move = game_state.choose_best_move(tie_break).unwrap();
+            moves.push(notation::format_move(&chosen_move));
+            game_state.apply_move(&chosen_move);
+        }
+        moves
+    };
+    for &tie_break in &[TieBreak::First,
+                        TieBreak::Random(1),
+                        TieBreak::PreferLonger,
+                        TieBreak::PreferKeepingDuplicates] {
+        assert!(play(tie_break) == play(tie_break));
+    }
+
+    // and every tied placement is a candidate, whatever order the generator found them in
+    let game_state = GameState::with_rules(2, Rules::standard(), 47);
+    let moves = game_state.generate_moves();
+    for pair in moves.windows(2) {
+        assert!(candidate_order(&pair[0], &pair[1]) == Ordering::Less);
+    }
+    let best = game_state.generate_best_move().unwrap();
+    let first_best = moves.iter().find(|m| m.score() == best.score()).unwrap();
+    assert!(notation::format_move(first_best) == notation::format_move(&best));
+}
@@ -0,0 +1,199 @@
+use gamestate::GameState;
+use direction::Square;
+use mv::Move;
+use notation;
+use render::{self, Overlay, RenderMode};
+use strategy::Strategy;
+use std::io::{self, BufRead, Write};
+
+/// Who is sitting in each seat of an interactive game.
+pub enum Seat {
+    Human,
+    Bot(Box<dyn Strategy>),
+}
+
+impl Seat {
+    pub fn name(&self) -> &str {
+        match *self {
+            Seat::Human => "human",
+            Seat::Bot(ref strategy) => strategy.name(),
+        }
+    }
+}
+
+const HELP: &'static str = "\
+Enter a move as a start square, a direction and the pieces to place, eg `0,0 R 11 12`.
+Pieces are two digits: colour (1-6) then shape (1-6).
+Directions: U (y + 1, down the screen), D (y - 1), L (x - 1), R (x + 1).
+Other commands: `swap` (exchange your whole hand), `hint [n]` (show the n best moves, 3 by
+default, and how they score), `help`, `quit`.";
+
+fn render_scores(game_state: &GameState, seats: &[Seat]) -> String {
+    let scores: Vec<String> = game_state.players
+                                        .iter()
+                                        .zip(seats.iter())
+                                        .enumerate()
+                                        .map(|(i, (player, seat))| {
+                                            format!("player {} ({}): {}",
+                                                    i + 1,
+                                                    seat.name(),
+                                                    player.score)
+                                        })
+                                        .collect();
+    return scores.join(", ");
+}
+
+/// The board, with the pieces on `last_squares` highlighted.
+fn draw(game_state: &GameState, last_squares: &[Square], mode: RenderMode, overlay: Overlay) -> String {
+    let dead = overlay.dead_spots(&game_state.board, game_state.rules.copies);
+    return render::render_with_dead(&game_state.board, last_squares, &dead, mode);
+}
+
+/// Runs a game where each seat is either a human (reading moves from `input`) or a bot.
+///
+/// Illegal moves are rejected with a reason and the human is asked again.  Returns early if
+/// `input` is exhausted or the human types `quit`.  Boards are drawn in `mode`, with `overlay`.
+pub fn play<R: BufRead, W: Write>(game_state: &mut GameState,
+                                  seats: &mut [Seat],
+                                  mode: RenderMode,
+                                  overlay: Overlay,
+                                  input: &mut R,
+                                  output: &mut W)
+                                  -> io::Result<()> {
+    assert!(seats.len() == game_state.players.len());
+    writeln!(output, "{}\n", HELP)?;
+    let mut last_squares = vec![];
+
+    while !game_state.is_over() {
+        let player = game_state.turn;
+
+        let chosen_move = match seats[player] {
+            Seat::Bot(ref mut strategy) => strategy.choose_move(game_state),
+            Seat::Human => {
+                writeln!(output, "{}", draw(game_state, &last_squares, mode, overlay))?;
+                writeln!(output, "{}", render_scores(game_state, seats))?;
+                writeln!(output, "{} pieces left in the bag", game_state.bag_size())?;
+                writeln!(output,
+                         "player {}, your hand: {}",
+                         player + 1,
+                         notation::format_pieces(&game_state.players[player].bag))?;
+                match read_human_move(game_state, input, output)? {
+                    None => return Ok(()),
+                    Some(m) => m,
+                }
+            }
+        };
+
+        match chosen_move {
+            Move::SwapPieces => writeln!(output, "player {} swaps", player + 1)?,
+            Move::PlacePieces(_, _, _, ref breakdown) => {
+                writeln!(output,
+                         "player {} plays {} for {}",
+                         player + 1,
+                         notation::format_move(&chosen_move),
+                         breakdown)?
+            }
+        }
+        game_state.apply_move(&chosen_move);
+        last_squares = chosen_move.squares();
+    }
+
+    writeln!(output, "{}", draw(game_state, &last_squares, mode, overlay))?;
+    writeln!(output, "Game finished: {}", render_scores(game_state, seats))?;
+    return Ok(());
+}
+
+fn write_hints<W: Write>(game_state: &GameState, n: usize, output: &mut W) -> io::Result<()> {
+    let hints = game_state.hints(n);
+    if hints.len() == 0 {
+        return writeln!(output, "Nothing can be placed, so you'll have to swap.");
+    }
+    for hint in hints {
+        writeln!(output,
+                 "  {:<24} {}",
+                 notation::format_move(&hint.chosen_move),
+                 hint.breakdown())?;
+    }
+    return Ok(());
+}
+
+fn read_human_move<R: BufRead, W: Write>(game_state: &GameState,
+                                         input: &mut R,
+                                         output: &mut W)
+                                         -> io::Result<Option<Move>> {
+    loop {
+        write!(output, "> ")?;
+        output.flush()?;
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        match line.trim() {
+            "" => continue,
+            "quit" => return Ok(None),
+            "help" => {
+                writeln!(output, "{}", HELP)?;
+                continue;
+            }
+            command if command.starts_with("hint") => {
+                match command["hint".len()..].trim() {
+                    "" => write_hints(game_state, 3, output)?,
+                    n => {
+                        match n.parse() {
+                            Ok(n) => write_hints(game_state, n, output)?,
+                            Err(_) => writeln!(output, "Expected `hint` or eg `hint 5`")?,
+                        }
+                    }
+                }
+                continue;
+            }
+            _ => {}
+        }
+        match notation::parse_move(&line) {
+            Err(reason) => writeln!(output, "Couldn't read that move: {}", reason)?,
+            Ok(proposed) => {
+                match game_state.validate_move(&proposed) {
+                    Err(reason) => writeln!(output, "Illegal move: {}", reason)?,
+                    Ok(legal) => return Ok(Some(legal)),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+use strategy::Greedy;
+
+#[test]
+fn play_should_reject_illegal_moves_and_stop_at_end_of_input() {
+    let mut game_state = GameState::new(2);
+    let mut input = io::Cursor::new("0,0 R 99\n5,5 R 11\nhint 2\nquit\n".as_bytes());
+    let mut output = vec![];
+    let mut seats = [Seat::Human, Seat::Bot(Box::new(Greedy))];
+    play(&mut game_state,
+         &mut seats,
+         RenderMode::Plain,
+         Overlay::Nothing,
+         &mut input,
+         &mut output)
+        .unwrap();
+    let text = String::from_utf8(output).unwrap();
+    assert!(text.contains("Couldn't read that move"));
+    assert!(text.contains("Illegal move"));
+    assert!(text.contains(" (main)"));
+    assert!(game_state.turn == 0);
+}
+
+#[test]
+fn play_should_run_bot_only_games_to_completion() {
+    let mut game_state = GameState::new(2);
+    let mut output = vec![];
+    play(&mut game_state,
+         &mut [Seat::Bot(Box::new(Greedy)), Seat::Bot(Box::new(Greedy))],
+         RenderMode::Plain,
+         Overlay::Nothing,
+         &mut io::Cursor::new(vec![]),
+         &mut output)
+        .unwrap();
+    assert!(String::from_utf8(output).unwrap().contains("Game finished"));
+}
@@ -0,0 +1,321 @@
+use std::fmt;
+
+/// A minimal JSON value, just enough for machine readable output and simple request bodies.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Convenience constructor for objects, preserving the order of `fields`.
+    pub fn object(fields: Vec<(&str, Json)>) -> Json {
+        return Json::Object(fields.into_iter().map(|(k, v)| (k.to_string(), v)).collect());
+    }
+
+    pub fn str(s: &str) -> Json {
+        return Json::Str(s.to_string());
+    }
+
+    /// Looks up a field of an object, returning `None` for missing fields and non-objects.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match *self {
+            Json::Object(ref fields) => fields.iter().find(|&&(ref k, _)| k == key).map(|&(_, ref v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            Json::Str(ref s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Json::Int(i) => Some(i),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<Json>> {
+        match *self {
+            Json::Array(ref items) => Some(items),
+            _ => None,
+        }
+    }
+
+    /// Parses a complete JSON document.
+    pub fn parse(s: &str) -> Result<Json, String> {
+        let mut parser = Parser {
+            chars: s.chars().collect(),
+            pos: 0,
+            depth: 0,
+        };
+        let value = parser.parse_value()?;
+        parser.skip_whitespace();
+        if parser.pos != parser.chars.len() {
+            return Err(format!("unexpected trailing characters at {}", parser.pos));
+        }
+        return Ok(value);
+    }
+}
+
+/// How deeply arrays and objects may nest.  The parser recurses, so without a limit a body
+/// like `[[[[...` could overflow the stack.
+const MAX_DEPTH: usize = 64;
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    /// How many arrays and objects enclose `pos`.
+    depth: usize,
+}
+
+impl Parser {
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.chars.len() && self.chars[self.pos].is_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        return self.chars.get(self.pos).cloned();
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_whitespace();
+        if self.peek() == Some(expected) {
+            self.pos += 1;
+            return Ok(());
+        }
+        return Err(format!("expected '{}' at {}", expected, self.pos));
+    }
+
+    fn parse_literal(&mut self, literal: &str, value: Json) -> Result<Json, String> {
+        for expected in literal.chars() {
+            if self.peek() != Some(expected) {
+                return Err(format!("expected '{}' at {}", literal, self.pos));
+            }
+            self.pos += 1;
+        }
+        return Ok(value);
+    }
+
+    fn parse_value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            None => Err("unexpected end of input".to_string()),
+            Some('n') => self.parse_literal("null", Json::Null),
+            Some('t') => self.parse_literal("true", Json::Bool(true)),
+            Some('f') => self.parse_literal("false", Json::Bool(false)),
+            Some('"') => self.parse_string().map(Json::Str),
+            Some(c) if c == '[' || c == '{' => {
+                if self.depth == MAX_DEPTH {
+                    return Err(format!("nested more than {} deep at {}", MAX_DEPTH, self.pos));
+                }
+                self.depth += 1;
+                let value = if c == '[' { self.parse_array() } else { self.parse_object() };
+                self.depth -= 1;
+                value
+            }
+            Some(c) if c == '-' || c.is_digit(10) => self.parse_number(),
+            Some(c) => Err(format!("unexpected '{}' at {}", c, self.pos)),
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<Json, String> {
+        self.pos += 1;
+        let mut items = vec![];
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.pos += 1;
+            return Ok(Json::Array(items));
+        }
+        loop {
+            items.push(self.parse_value()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some(']') => {
+                    self.pos += 1;
+                    return Ok(Json::Array(items));
+                }
+                _ => return Err(format!("expected ',' or ']' at {}", self.pos)),
+            }
+        }
+    }
+
+    fn parse_object(&mut self) -> Result<Json, String> {
+        self.pos += 1;
+        let mut fields = vec![];
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.pos += 1;
+            return Ok(Json::Object(fields));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.parse_string()?;
+            self.expect(':')?;
+            fields.push((key, self.parse_value()?));
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some('}') => {
+                    self.pos += 1;
+                    return Ok(Json::Object(fields));
+                }
+                _ => return Err(format!("expected ',' or '}}' at {}", self.pos)),
+            }
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<Json, String> {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if c.is_digit(10) || c == '-' || c == '+' || c == '.' || c == 'e' || c == 'E' {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        let text: String = self.chars[start..self.pos].iter().cloned().collect();
+        if let Ok(i) = text.parse::<i64>() {
+            return Ok(Json::Int(i));
+        }
+        match text.parse::<f64>() {
+            Ok(f) => Ok(Json::Float(f)),
+            Err(_) => Err(format!("'{}' is not a number", text)),
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, String> {
+        if self.peek() != Some('"') {
+            return Err(format!("expected a string at {}", self.pos));
+        }
+        self.pos += 1;
+        let mut s = String::new();
+        loop {
+            let c = match self.peek() {
+                None => return Err("unterminated string".to_string()),
+                Some(c) => c,
+            };
+            self.pos += 1;
+            match c {
+                '"' => return Ok(s),
+                '\\' => {
+                    let escaped = match self.peek() {
+                        None => return Err("unterminated string".to_string()),
+                        Some(e) => e,
+                    };
+                    self.pos += 1;
+                    match escaped {
+                        '"' => s.push('"'),
+                        '\\' => s.push('\\'),
+                        '/' => s.push('/'),
+                        'n' => s.push('\n'),
+                        'r' => s.push('\r'),
+                        't' => s.push('\t'),
+                        'b' => s.push('\u{8}'),
+                        'f' => s.push('\u{c}'),
+                        'u' => {
+                            let hex: String = self.chars[self.pos..].iter().take(4).cloned().collect();
+                            let code = match u32::from_str_radix(&hex, 16) {
+                                Ok(code) if hex.len() == 4 => code,
+                                _ => return Err(format!("bad unicode escape at {}", self.pos)),
+                            };
+                            self.pos += 4;
+                            s.push(::std::char::from_u32(code).unwrap_or('\u{fffd}'));
+                        }
+                        _ => return Err(format!("bad escape '\\{}' at {}", escaped, self.pos)),
+                    }
+                }
+                c => s.push(c),
+            }
+        }
+    }
+}
+
+fn write_escaped(formatter: &mut fmt::Formatter, s: &str) -> fmt::Result {
+    write!(formatter, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(formatter, "\\\"")?,
+            '\\' => write!(formatter, "\\\\")?,
+            '\n' => write!(formatter, "\\n")?,
+            '\r' => write!(formatter, "\\r")?,
+            '\t' => write!(formatter, "\\t")?,
+            c if (c as u32) < 0x20 => write!(formatter, "\\u{:04x}", c as u32)?,
+            c => write!(formatter, "{}", c)?,
+        }
+    }
+    write!(formatter, "\"")
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Json::Null => write!(formatter, "null"),
+            Json::Bool(b) => write!(formatter, "{}", b),
+            Json::Int(i) => write!(formatter, "{}", i),
+            Json::Float(f) if f.is_finite() => write!(formatter, "{:?}", f),
+            Json::Float(_) => write!(formatter, "null"),
+            Json::Str(ref s) => write_escaped(formatter, s),
+            Json::Array(ref items) => {
+                write!(formatter, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(formatter, ",")?;
+                    }
+                    write!(formatter, "{}", item)?;
+                }
+                write!(formatter, "]")
+            }
+            Json::Object(ref fields) => {
+                write!(formatter, "{{")?;
+                for (i, &(ref key, ref value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(formatter, ",")?;
+                    }
+                    write_escaped(formatter, key)?;
+                    write!(formatter, ":{}", value)?;
+                }
+                write!(formatter, "}}")
+            }
+        }
+    }
+}
+
+#[test]
+fn display_should_escape_strings_and_nest() {
+    let value = Json::object(vec![("name", Json::str("a \"b\"\n")),
+                                  ("scores", Json::Array(vec![Json::Int(1), Json::Float(2.5)])),
+                                  ("done", Json::Bool(true))]);
+    assert!(value.to_string() == r#"{"name":"a \"b\"\n","scores":[1,2.5],"done":true}"#);
+}
+
+#[test]
+fn parse_should_read_what_display_writes() {
+    let text = r#" {"name": "a \"b\"\n\u0041", "n": [-3, 2.5e1, true, null], "o": {}} "#;
+    let value = Json::parse(text).unwrap();
+    assert!(value.get("name").and_then(|v| v.as_str()) == Some("a \"b\"\nA"));
+    assert!(value.get("n").and_then(|v| v.as_array()).map(|a| a.len()) == Some(4));
+    assert!(Json::parse(&value.to_string()) == Ok(value));
+}
+
+#[test]
+fn parse_should_reject_malformed_documents() {
+    assert!(Json::parse("{\"a\": }").is_err());
+    assert!(Json::parse("[1, 2").is_err());
+    assert!(Json::parse("\"open").is_err());
+    assert!(Json::parse("1 2").is_err());
+    assert!(Json::parse(&"[".repeat(100000)).is_err());
+    assert!(Json::parse(&format!("{}{}", "[".repeat(64), "]".repeat(64))).is_ok());
+}
@@ -0,0 +1,81 @@
+//! Qwirkle rules, move generation and computer players.
+//!
+//! A game is driven through `GameState`: ask it for moves (`generate_moves`,
+//! `generate_best_move`), check a move with `validate_move` and play it with `apply_move`.
+//! `Strategy` implementations choose moves for a seat, and `notation` reads and writes the
+//! compact text forms of pieces, moves and positions used by the command line tools.
+//!
+//! ```
+//! use qwirkler::{GameState, Rules, Strategy, Greedy};
+//!
+//! let mut game_state = GameState::with_rules(2, Rules::standard(), 42);
+//! let mut greedy = Greedy;
+//! while !game_state.is_over() {
+//!     let chosen_move = greedy.choose_move(&game_state);
+//!     game_state.apply_move(&chosen_move);
+//! }
+//! assert!(game_state.total_score() > 0);
+//! ```
+
+// The benchmarks need the unstable test crate, so they are behind the bench feature and
+// only build on nightly: `cargo +nightly bench --features bench`.
+#![cfg_attr(feature = "bench", feature(test))]
+
+extern crate rand;
+
+pub mod notation;
+
+mod piece;
+mod board;
+mod gamestate;
+mod direction;
+mod player;
+mod partial;
+#[cfg(all(test, feature = "bench"))]
+mod bench;
+#[cfg(test)]
+mod properties;
+mod linevalidator;
+mod mv;
+mod interactive;
+mod rules;
+mod strategy;
+mod json;
+mod render;
+mod svg;
+mod view;
+mod server;
+mod search;
+mod engine;
+mod external;
+mod ffi;
+mod lobby;
+mod zobrist;
+#[cfg(test)]
+mod reference;
+mod dead;
+
+// The command line front-end, for src/main.rs.  It isn't part of the library's API.
+#[doc(hidden)]
+pub mod cli;
+
+// Fuzz targets, for fuzz/ (which turns the feature on) and the test that drives them.
+#[cfg(any(test, feature = "fuzz"))]
+#[doc(hidden)]
+pub mod fuzz;
+
+pub use board::{Board, CanonicalBoard, CrossCheck, Line, LineAttribute, Occupied, Symmetry};
+pub use dead::DeadSpots;
+pub use direction::{Direction, Square};
+pub use gamestate::{GameState, GenerationStats, Hint, TieBreak, best_placement, candidate_order,
+                    visit_partials};
+pub use linevalidator::LineValidator;
+pub use mv::{IllegalMove, LineScore, Move, ScoreBreakdown};
+pub use partial::Partial;
+pub use piece::{Bag, Colour, Piece, Shape};
+pub use player::{PlayerState, Score};
+pub use rules::Rules;
+pub use strategy::{Greedy, GreedyTieBreak, RandomMoves, Strategy};
+pub use search::{Search, SearchStats};
+pub use external::{ExternalEngine, IllegalReply};
+pub use view::PlayerView;
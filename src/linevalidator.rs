@@ -1,71 +1,102 @@
-use piece::{Piece, Colour, Shape};
+use piece::Piece;
+#[cfg(test)]
+use piece::{Colour, Shape};
 
+/// Each piece's colour (bits 0-5) and shape (bits 6-11), indexed by `Piece::index()`.
+const ATTRIBUTES: [u16; 36] = attribute_table();
+
+/// Every piece with a given colour (entries 0-5) or shape (entries 6-11), as a bitmask of
+/// `Piece::index()`es.
+const PIECES_WITH: [u64; 12] = pieces_with_table();
+
+const fn attribute_table() -> [u16; 36] {
+    let mut table = [0; 36];
+    let mut i = 0;
+    while i < 36 {
+        table[i] = 1 << (i / 6) | 1 << (6 + i % 6);
+        i += 1;
+    }
+    return table;
+}
+
+const fn pieces_with_table() -> [u64; 12] {
+    let mut table = [0; 12];
+    let mut i = 0;
+    while i < 36 {
+        table[i / 6] |= 1 << i;
+        table[6 + i % 6] |= 1 << i;
+        i += 1;
+    }
+    return table;
+}
+
+/// At most six pieces, no two the same, and all of them sharing a colour or a shape.  This is
+/// the rule written out directly, for checking the board and `LineValidator` against.
+pub fn is_valid_line(line: &[Piece]) -> bool {
+    return line.len() <= 6 && line.iter().enumerate().all(|(i, p)| !line[..i].contains(p)) &&
+           (line.iter().all(|p| p.colour == line[0].colour) ||
+            line.iter().all(|p| p.shape == line[0].shape));
+}
+
+/// Checks that a line of pieces is legal: no piece appears twice, all of them share a colour
+/// or a shape, and there are at most six.
+///
+/// The pieces are kept as a bitmask of their indexes, along with a mask of the attributes they
+/// all have in common, so adding a piece is a few bit operations.  The order that pieces are
+/// added in doesn't matter.
 #[derive(Copy, Clone)]
 pub struct LineValidator {
-    seen_already: [bool; 6],
-    first_piece: Piece,
-    is_line_of_colour: Option<bool>,
+    /// Bit `piece.index()` is set for every piece in the line.
+    pieces: u64,
+    /// The colours and shapes, as in `ATTRIBUTES`, that every piece in the line has.
+    shared: u16,
     pub length: usize,
 }
 
 impl LineValidator {
     pub fn new(first_piece: Piece) -> LineValidator {
+        let mut lv = LineValidator::empty();
+        lv.add_piece(first_piece);
+        return lv;
+    }
+
+    /// A line with no pieces in it yet, which any piece may start.
+    pub fn empty() -> LineValidator {
         return LineValidator {
-            seen_already: [false; 6],
-            first_piece: first_piece,
-            is_line_of_colour: None,
-            length: 1,
+            pieces: 0,
+            shared: (1 << 12) - 1,
+            length: 0,
         };
     }
 
+    /// Every piece that could be added to the line, as a bitmask of `Piece::index()`es.
+    pub fn allowed(&self) -> u64 {
+        if self.length >= 6 {
+            return 0;
+        }
+        let mut allowed = 0;
+        for attribute in 0..12 {
+            if self.shared & (1 << attribute) != 0 {
+                allowed |= PIECES_WITH[attribute];
+            }
+        }
+        return allowed & !self.pieces;
+    }
+
+    fn accepts(&self, new_piece: Piece) -> bool {
+        let index = new_piece.index();
+        return self.length < 6 && self.pieces & (1 << index) == 0 &&
+               self.shared & ATTRIBUTES[index] != 0;
+    }
+
     /// Clones and extends this validator if the new_piece is compatible, returns None otherwise
     pub fn clone_extend(&self, new_piece: Piece) -> Option<LineValidator> {
-        if self.length == 6 || new_piece == self.first_piece {
+        if !self.accepts(new_piece) {
             return None;
         }
-
-        match self.is_line_of_colour {
-            None => {
-                if self.first_piece.colour == new_piece.colour {
-                    let mut lv2 = *self;
-                    lv2.is_line_of_colour = Some(true);
-                    lv2.seen_already[self.first_piece.shape.index()] = true;
-                    lv2.seen_already[new_piece.shape.index()] = true;
-                    lv2.length += 1;
-                    return Some(lv2);
-                } else if self.first_piece.shape == new_piece.shape {
-                    let mut lv2 = *self;
-                    lv2.is_line_of_colour = Some(false);
-                    lv2.seen_already[self.first_piece.colour.index()] = true;
-                    lv2.seen_already[new_piece.colour.index()] = true;
-                    lv2.length += 1;
-                    return Some(lv2);
-                } else {
-                    return None;
-                }
-            }
-            Some(loc) => {
-                if loc {
-                    if self.first_piece.colour != new_piece.colour ||
-                       self.seen_already[new_piece.shape.index()] {
-                        return None;
-                    }
-                    let mut lv2 = *self;
-                    lv2.seen_already[new_piece.shape.index()] = true;
-                    lv2.length += 1;
-                    return Some(lv2);
-                } else {
-                    if self.first_piece.shape != new_piece.shape ||
-                       self.seen_already[new_piece.colour.index()] {
-                        return None;
-                    }
-                    let mut lv2 = *self;
-                    lv2.seen_already[new_piece.colour.index()] = true;
-                    lv2.length += 1;
-                    return Some(lv2);
-                }
-            }
-        }
+        let mut lv2 = *self;
+        lv2.push(new_piece);
+        return Some(lv2);
     }
 
     /// prevents any more extensions
@@ -89,54 +120,18 @@ impl LineValidator {
     }
 
     fn add_piece(&mut self, new_piece: Piece) -> bool {
-        match self.is_line_of_colour {
-            None => {
-                if self.first_piece.colour == new_piece.colour {
-                    if self.length == 6 || new_piece == self.first_piece {
-                        return false;
-                    }
-                    self.is_line_of_colour = Some(true);
-                    self.seen_already[self.first_piece.shape.index()] = true;
-                    self.seen_already[new_piece.shape.index()] = true;
-                    self.length = self.length + 1;
-                    return true;
-                } else if self.first_piece.shape == new_piece.shape {
-                    if self.length == 6 || new_piece == self.first_piece {
-                        return false;
-                    }
-                    self.is_line_of_colour = Some(false);
-                    self.seen_already[self.first_piece.colour.index()] = true;
-                    self.seen_already[new_piece.colour.index()] = true;
-                    self.length = self.length + 1;
-                    return true;
-                } else {
-                    return false;
-                }
-            }
-            Some(loc) => {
-                if loc {
-                    if self.first_piece.colour != new_piece.colour ||
-                       self.seen_already[new_piece.shape.index()] {
-                        return false;
-                    }
-                    if self.length == 6 || new_piece == self.first_piece {
-                        return false;
-                    }
-                    self.seen_already[new_piece.shape.index()] = true;
-                } else {
-                    if self.first_piece.shape != new_piece.shape ||
-                       self.seen_already[new_piece.colour.index()] {
-                        return false;
-                    }
-                    if self.length == 6 || new_piece == self.first_piece {
-                        return false;
-                    }
-                    self.seen_already[new_piece.colour.index()] = true;
-                }
-                self.length = self.length + 1;
-                return true;
-            }
+        if !self.accepts(new_piece) {
+            return false;
         }
+        self.push(new_piece);
+        return true;
+    }
+
+    fn push(&mut self, new_piece: Piece) {
+        let index = new_piece.index();
+        self.pieces |= 1 << index;
+        self.shared &= ATTRIBUTES[index];
+        self.length += 1;
     }
 }
 
@@ -164,30 +159,40 @@ fn test_add_same_shape() {
 }
 
 #[test]
-#[should_panic]
 fn test_add_identical_fail() {
     let p1 = Piece::new(Colour::R, Shape::A);
     let mut lv = LineValidator::new(p1);
-    assert!(lv.add_piece(p1));
+    assert!(!lv.add_piece(p1));
 }
 
 #[test]
-#[should_panic]
 fn test_duplicate_first() {
     let p1 = Piece::new(Colour::R, Shape::A);
     let p2 = Piece::new(Colour::G, Shape::A);
     let mut lv = LineValidator::new(p1);
-    lv.add_piece(p2);
-    assert!(lv.add_piece(p1));
+    assert!(lv.add_piece(p2));
+    assert!(!lv.add_piece(p1));
 }
 
 #[test]
-#[should_panic]
 fn test_change_common_feature() {
     let p1 = Piece::new(Colour::R, Shape::A);
     let p2 = Piece::new(Colour::G, Shape::A);
     let p3 = Piece::new(Colour::G, Shape::B);
     let lv = LineValidator::new(p1);
     let lv2 = lv.clone_extend(p2).unwrap();
-    assert!(lv2.clone_extend(p3).is_some());
+    assert!(lv2.clone_extend(p3).is_none());
+}
+
+#[test]
+fn allowed_should_list_the_pieces_that_fit() {
+    let ra = Piece::new(Colour::R, Shape::A);
+    let rb = Piece::new(Colour::R, Shape::B);
+    let lv = LineValidator::new(ra);
+    // the other five shapes in red and the other five colours of shape A
+    assert!(lv.allowed().count_ones() == 10);
+    let lv2 = lv.clone_extend(rb).unwrap();
+    assert!(lv2.allowed().count_ones() == 4);
+    assert!(lv2.allowed() & (1 << Piece::new(Colour::G, Shape::A).index()) == 0);
+    assert!(LineValidator::empty().allowed().count_ones() == 36);
 }
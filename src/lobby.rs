@@ -0,0 +1,666 @@
+use gamestate::GameState;
+use mv::Move;
+use notation;
+use rand::{thread_rng, Rng, SeedableRng, StdRng};
+use rules::Rules;
+use strategy::{self, Strategy};
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+/// A multiplayer game host for remote players, speaking a line based protocol over TCP in the
+/// same style as `engine.rs`.
+///
+/// Clients send one command per line:
+///
+/// * `hello <name>` sets the name shown to other players.
+/// * `list` replies with `table <id> <taken>/<seats> waiting|playing|over` for every table, then
+///   `end`.
+/// * `create <seats> [rules]` opens a table, eg `create 3 hand=4`, and replies `created <id>`.
+/// * `join <id> [seat]` takes the given seat, or the first free one, and replies
+///   `seated <id> <seat> <token>`.  The token is needed to get the seat back after a dropped
+///   connection.
+/// * `start` begins the game at your table, filling any free seats with bots.  A game also
+///   starts by itself once every seat is taken.
+/// * `move <move>` plays a move in move notation, eg `move 0,0 R 11 12` or `move swap`.
+/// * `resume <token>` takes a seat back after reconnecting.
+/// * `quit` closes the connection.  The seat is kept for `resume`.
+///
+/// Once a game is running, every seated player is sent `position <board> | <their hand>`,
+/// `scores <n> ...`, `bag <n>` and `turn <seat>`, followed by `yourturn` for the player who is
+/// to move.  Other players' hands are never sent.  Every move is announced as
+/// `moved <seat> <score> <move>`, players joining or dropping out as `joined <seat> <name>` and
+/// `left <seat>`, and the end of the game as `gameover <scores>`.  Mistakes are answered with
+/// `error <reason>` and change nothing.
+///
+/// The game waits for a disconnected player to resume; their turn is not skipped.  Once it is
+/// over, its players are free to join or create another table.
+///
+/// Lines longer than `MAX_LINE` bytes close the connection.
+pub struct Lobby {
+    tables: HashMap<usize, Table>,
+    clients: HashMap<ClientId, Client>,
+    next_table: usize,
+    bot: String,
+    rng: StdRng,
+    outbox: Vec<(ClientId, String)>,
+}
+
+pub type ClientId = usize;
+
+/// The longest line a client may send, not counting the newline.
+pub const MAX_LINE: usize = 1024;
+
+struct Client {
+    name: String,
+    /// The table and seat this client is playing at.
+    seat: Option<(usize, usize)>,
+}
+
+struct Table {
+    rules: Rules,
+    seats: Vec<Option<SeatHolder>>,
+    /// `None` until the game starts.
+    state: Option<GameState>,
+    /// The bots filling seats nobody took, indexed by seat.
+    bots: Vec<Option<Box<dyn Strategy>>>,
+}
+
+struct SeatHolder {
+    name: String,
+    /// What `resume` needs to take the seat back.  Bots have none, so their seats can't be taken.
+    token: Option<String>,
+    /// `None` while the player is disconnected.
+    client: Option<ClientId>,
+}
+
+impl Table {
+    fn status(&self) -> &'static str {
+        match self.state {
+            None => "waiting",
+            Some(ref state) if state.is_over() => "over",
+            Some(_) => "playing",
+        }
+    }
+
+    fn connected(&self) -> Vec<(usize, ClientId)> {
+        return self.seats
+                   .iter()
+                   .enumerate()
+                   .filter_map(|(seat, holder)| holder.as_ref().and_then(|h| h.client).map(|c| (seat, c)))
+                   .collect();
+    }
+}
+
+impl Lobby {
+    /// `bot` is the strategy that fills empty seats, and `seed` determines every table's bag.
+    /// Resume tokens don't depend on the seed, so knowing it doesn't let anyone take a seat.
+    pub fn new(bot: &str, seed: usize) -> Lobby {
+        return Lobby {
+            tables: HashMap::new(),
+            clients: HashMap::new(),
+            next_table: 1,
+            bot: bot.to_string(),
+            rng: SeedableRng::from_seed(&[seed][..]),
+            outbox: vec![],
+        };
+    }
+
+    /// The lines to send since the last call, in order, with the client each one is for.
+    pub fn take_messages(&mut self) -> Vec<(ClientId, String)> {
+        return ::std::mem::replace(&mut self.outbox, vec![]);
+    }
+
+    fn send(&mut self, client: ClientId, line: String) {
+        self.outbox.push((client, line));
+    }
+
+    fn broadcast(&mut self, table: usize, line: String) {
+        for (_, client) in self.tables[&table].connected() {
+            self.send(client, line.clone());
+        }
+    }
+
+    pub fn connect(&mut self, client: ClientId) {
+        self.clients.insert(client,
+                            Client {
+                                name: format!("player{}", client),
+                                seat: None,
+                            });
+        self.send(client, "welcome qwirkler".to_string());
+    }
+
+    /// Forgets the connection but keeps its seat, so the player can `resume`.
+    pub fn disconnect(&mut self, client: ClientId) {
+        if let Some(Client { seat: Some((table, seat)), .. }) = self.clients.remove(&client) {
+            if let Some(ref mut holder) = self.tables.get_mut(&table).unwrap().seats[seat] {
+                if holder.client == Some(client) {
+                    holder.client = None;
+                }
+            }
+            self.broadcast(table, format!("left {}", seat));
+        }
+    }
+
+    /// Handles one line from `client`, returning true if the connection should be closed.
+    pub fn handle(&mut self, client: ClientId, line: &str) -> bool {
+        let line = line.trim();
+        let (command, args) = match line.find(' ') {
+            None => (line, ""),
+            Some(i) => (&line[..i], line[i + 1..].trim()),
+        };
+        let result = match command {
+            "" => Ok(()),
+            "quit" => return true,
+            "hello" if args.len() > 0 && !args.contains(char::is_whitespace) => {
+                self.clients.get_mut(&client).unwrap().name = args.to_string();
+                Ok(())
+            }
+            "hello" => Err("expected hello <name>, with no spaces in the name".to_string()),
+            "list" => {
+                let mut ids: Vec<usize> = self.tables.keys().cloned().collect();
+                ids.sort();
+                for id in ids {
+                    let line = {
+                        let table = &self.tables[&id];
+                        format!("table {} {}/{} {}",
+                                id,
+                                table.seats.iter().filter(|s| s.is_some()).count(),
+                                table.seats.len(),
+                                table.status())
+                    };
+                    self.send(client, line);
+                }
+                self.send(client, "end".to_string());
+                Ok(())
+            }
+            "create" => self.create(client, args),
+            "join" => self.join(client, args),
+            "start" => {
+                match self.clients[&client].seat {
+                    None => Err("you aren't at a table".to_string()),
+                    Some((table, _)) if self.tables[&table].state.is_some() => {
+                        Err("the game has already started".to_string())
+                    }
+                    Some((table, _)) => {
+                        self.start(table);
+                        Ok(())
+                    }
+                }
+            }
+            "move" => self.submit_move(client, args),
+            "resume" => self.resume(client, args),
+            _ => Err(format!("unknown command '{}'", command)),
+        };
+        if let Err(reason) = result {
+            self.send(client, format!("error {}", reason));
+        }
+        return false;
+    }
+
+    fn create(&mut self, client: ClientId, args: &str) -> Result<(), String> {
+        let words: Vec<&str> = args.split_whitespace().collect();
+        let seats = match words.get(0).map(|s| s.parse::<usize>()) {
+            Some(Ok(seats)) if seats >= 1 && seats <= 6 => seats,
+            _ => return Err("expected create <seats> [rules], with 1 to 6 seats".to_string()),
+        };
+        let rules = match words.get(1) {
+            None => Rules::standard(),
+            Some(spec) => Rules::parse(spec)?,
+        };
+        if seats * rules.hand_size > 36 * rules.copies {
+            return Err("there aren't enough pieces to fill every hand".to_string());
+        }
+        let id = self.next_table;
+        self.next_table += 1;
+        self.tables.insert(id,
+                           Table {
+                               rules: rules,
+                               seats: (0..seats).map(|_| None).collect(),
+                               state: None,
+                               bots: vec![],
+                           });
+        self.send(client, format!("created {}", id));
+        return Ok(());
+    }
+
+    fn join(&mut self, client: ClientId, args: &str) -> Result<(), String> {
+        if self.clients[&client].seat.is_some() {
+            return Err("you already have a seat".to_string());
+        }
+        let words: Vec<&str> = args.split_whitespace().collect();
+        let id = match words.get(0).map(|s| s.parse::<usize>()) {
+            Some(Ok(id)) if self.tables.contains_key(&id) => id,
+            _ => return Err("expected join <table> [seat] for an existing table".to_string()),
+        };
+        let seat = {
+            let table = &self.tables[&id];
+            if table.state.is_some() {
+                return Err("that game has already started".to_string());
+            }
+            match words.get(1).map(|s| s.parse::<usize>()) {
+                None => {
+                    match table.seats.iter().position(|s| s.is_none()) {
+                        Some(seat) => seat,
+                        None => return Err("that table is full".to_string()),
+                    }
+                }
+                Some(Ok(seat)) if seat < table.seats.len() && table.seats[seat].is_none() => seat,
+                Some(_) => return Err("that seat isn't free".to_string()),
+            }
+        };
+        let token = format!("{:016x}", thread_rng().gen::<u64>());
+        let name = self.clients[&client].name.clone();
+        self.tables.get_mut(&id).unwrap().seats[seat] = Some(SeatHolder {
+            name: name.clone(),
+            token: Some(token.clone()),
+            client: Some(client),
+        });
+        self.clients.get_mut(&client).unwrap().seat = Some((id, seat));
+        self.send(client, format!("seated {} {} {}", id, seat, token));
+        self.broadcast(id, format!("joined {} {}", seat, name));
+        if self.tables[&id].seats.iter().all(|s| s.is_some()) {
+            self.start(id);
+        }
+        return Ok(());
+    }
+
+    fn resume(&mut self, client: ClientId, token: &str) -> Result<(), String> {
+        if self.clients[&client].seat.is_some() {
+            return Err("you already have a seat".to_string());
+        }
+        if token.is_empty() {
+            return Err("expected resume <token>".to_string());
+        }
+        let mut found = None;
+        for (&id, table) in &self.tables {
+            for (seat, holder) in table.seats.iter().enumerate() {
+                if holder.as_ref().and_then(|h| h.token.as_ref()).map(|t| t == token) == Some(true) {
+                    found = Some((id, seat));
+                }
+            }
+        }
+        let (id, seat) = match found {
+            None => return Err("unknown token".to_string()),
+            Some(found) => found,
+        };
+        let previous = {
+            let holder = self.tables.get_mut(&id).unwrap().seats[seat].as_mut().unwrap();
+            ::std::mem::replace(&mut holder.client, Some(client))
+        };
+        // a stale connection that never noticed it dropped loses the seat
+        if let Some(previous) = previous {
+            if let Some(old) = self.clients.get_mut(&previous) {
+                old.seat = None;
+            }
+        }
+        self.clients.get_mut(&client).unwrap().seat = Some((id, seat));
+        self.send(client, format!("seated {} {} {}", id, seat, token));
+        let name = self.tables[&id].seats[seat].as_ref().unwrap().name.clone();
+        self.broadcast(id, format!("joined {} {}", seat, name));
+        if self.tables[&id].state.is_some() {
+            self.announce(id);
+        }
+        return Ok(());
+    }
+
+    fn start(&mut self, id: usize) {
+        let seed = self.rng.gen::<usize>();
+        let bot = self.bot.clone();
+        {
+            let table = self.tables.get_mut(&id).unwrap();
+            let seats = table.seats.len();
+            table.bots = table.seats
+                              .iter()
+                              .enumerate()
+                              .map(|(seat, holder)| match *holder {
+                                  Some(_) => None,
+                                  None => Some(strategy::from_name(&bot, seed.wrapping_add(seat + 1)).unwrap()),
+                              })
+                              .collect();
+            for seat in 0..seats {
+                if table.seats[seat].is_none() {
+                    table.seats[seat] = Some(SeatHolder {
+                        name: format!("{}-bot", bot),
+                        token: None,
+                        client: None,
+                    });
+                }
+            }
+            table.state = Some(GameState::with_rules(seats, table.rules, seed));
+        }
+        let names: Vec<String> = self.tables[&id]
+                                     .seats
+                                     .iter()
+                                     .map(|holder| holder.as_ref().unwrap().name.clone())
+                                     .collect();
+        self.broadcast(id, format!("start {}", names.join(" ")));
+        self.play_bots(id);
+        self.announce(id);
+    }
+
+    fn submit_move(&mut self, client: ClientId, args: &str) -> Result<(), String> {
+        let (id, seat) = match self.clients[&client].seat {
+            None => return Err("you aren't at a table".to_string()),
+            Some(seat) => seat,
+        };
+        let legal = {
+            let state = match self.tables[&id].state {
+                None => return Err("the game hasn't started".to_string()),
+                Some(ref state) => state,
+            };
+            if state.is_over() || state.turn != seat {
+                return Err("it isn't your turn".to_string());
+            }
+            let proposed = notation::parse_move(args)?;
+            state.validate_move(&proposed).map_err(|reason| format!("illegal move: {}", reason))?
+        };
+        self.play(id, &legal);
+        self.play_bots(id);
+        self.announce(id);
+        return Ok(());
+    }
+
+    fn play(&mut self, id: usize, chosen_move: &Move) {
+        let line = {
+            let state = self.tables.get_mut(&id).unwrap().state.as_mut().unwrap();
+            let line = format!("moved {} {} {}",
+                               state.turn,
+                               chosen_move.score(),
+                               notation::format_move(chosen_move));
+            state.apply_move(chosen_move);
+            line
+        };
+        self.broadcast(id, line);
+    }
+
+    fn play_bots(&mut self, id: usize) {
+        loop {
+            let chosen_move = {
+                let table = self.tables.get_mut(&id).unwrap();
+                let state = table.state.as_ref().unwrap();
+                if state.is_over() {
+                    return;
+                }
+                match table.bots[state.turn] {
+                    None => return,
+                    Some(ref mut bot) => bot.choose_move(state),
+                }
+            };
+            self.play(id, &chosen_move);
+        }
+    }
+
+    /// Tells every connected player what they can see of the game, and whose turn it is.
+    fn announce(&mut self, id: usize) {
+        let mut lines = vec![];
+        let mut players = vec![];
+        {
+            let table = &self.tables[&id];
+            let state = table.state.as_ref().unwrap();
+            let over = state.is_over();
+            for (seat, client) in table.connected() {
+                let view = state.view(seat);
+                let scores: Vec<String> = view.scores.iter().map(|s| s.to_string()).collect();
+                lines.push((client, format!("position {}", view.position())));
+                lines.push((client, format!("scores {}", scores.join(" "))));
+                lines.push((client, format!("bag {}", view.bag_size)));
+                if over {
+                    lines.push((client, format!("gameover {}", scores.join(" "))));
+                    players.push(client);
+                } else {
+                    lines.push((client, format!("turn {}", state.turn)));
+                    if state.turn == seat {
+                        lines.push((client, "yourturn".to_string()));
+                    }
+                }
+            }
+        }
+        self.outbox.extend(lines);
+
+        // the game is over, so its players can sit down somewhere else
+        for client in players {
+            self.clients.get_mut(&client).unwrap().seat = None;
+            for holder in self.tables.get_mut(&id).unwrap().seats.iter_mut() {
+                if let Some(ref mut holder) = *holder {
+                    if holder.client == Some(client) {
+                        holder.client = None;
+                    }
+                }
+            }
+        }
+    }
+}
+
+enum Event {
+    /// A new connection, with where to queue the lines to send it.
+    Connected(ClientId, Sender<String>),
+    Line(ClientId, String),
+    Dropped(ClientId),
+}
+
+/// Passes on a client's lines until it disconnects, sends something that isn't UTF-8, or sends
+/// a line longer than `MAX_LINE`.
+fn read_lines<R: Read>(client: ClientId, stream: R, events: Sender<Event>) {
+    let mut reader = BufReader::new(stream);
+    loop {
+        let mut line = String::new();
+        match reader.by_ref().take(MAX_LINE as u64 + 1).read_line(&mut line) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+        if !line.ends_with('\n') && line.len() > MAX_LINE {
+            break;
+        }
+        let line = line.trim_end_matches(&['\n', '\r'][..]).to_string();
+        if events.send(Event::Line(client, line)).is_err() {
+            return;
+        }
+    }
+    let _ = events.send(Event::Dropped(client));
+}
+
+/// Sends a client its lines on a thread of its own, so a client that stops reading can't hold
+/// up the lobby.  Once `lines` is closed, whatever is still queued is sent and the connection
+/// is shut.
+fn write_lines(mut stream: TcpStream, lines: Receiver<String>) {
+    for line in lines {
+        if writeln!(stream, "{}", line).is_err() {
+            // the reading side sees the dropped connection
+            return;
+        }
+    }
+    let _ = stream.shutdown(Shutdown::Both);
+}
+
+/// Hosts games for every connection to `listener` until the process is killed.  Each connection
+/// is read and written on threads of its own, but the lobby itself runs on this one.
+pub fn host(listener: TcpListener, mut lobby: Lobby) -> io::Result<()> {
+    let (events, receiver) = mpsc::channel();
+    let acceptor = events.clone();
+    thread::spawn(move || {
+        for (client, stream) in listener.incoming().enumerate() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    let _ = writeln!(io::stderr(), "connection failed: {}", e);
+                    continue;
+                }
+            };
+            let reader = match stream.try_clone() {
+                Ok(reader) => reader,
+                Err(_) => continue,
+            };
+            let (lines, queued) = mpsc::channel();
+            if acceptor.send(Event::Connected(client, lines)).is_err() {
+                return;
+            }
+            thread::spawn(move || write_lines(stream, queued));
+            let events = acceptor.clone();
+            thread::spawn(move || read_lines(client, reader, events));
+        }
+    });
+    drop(events);
+
+    // dropping a client's sender closes its connection once the queued lines are sent
+    let mut writers: HashMap<ClientId, Sender<String>> = HashMap::new();
+    for event in receiver {
+        match event {
+            Event::Connected(client, lines) => {
+                writers.insert(client, lines);
+                lobby.connect(client);
+            }
+            Event::Line(client, line) => {
+                if lobby.handle(client, &line) {
+                    writers.remove(&client);
+                    lobby.disconnect(client);
+                }
+            }
+            Event::Dropped(client) => {
+                writers.remove(&client);
+                lobby.disconnect(client);
+            }
+        }
+        for (client, line) in lobby.take_messages() {
+            if let Some(lines) = writers.get(&client) {
+                let _ = lines.send(line);
+            }
+        }
+    }
+    return Ok(());
+}
+
+#[cfg(test)]
+fn lines_for(messages: &[(ClientId, String)], client: ClientId) -> Vec<String> {
+    return messages.iter().filter(|m| m.0 == client).map(|m| m.1.clone()).collect();
+}
+
+#[test]
+fn lobby_should_seat_players_and_only_show_them_their_own_hand() {
+    let mut lobby = Lobby::new("greedy", 3);
+    lobby.connect(1);
+    lobby.connect(2);
+    lobby.handle(1, "create 2");
+    lobby.handle(1, "join 1");
+    lobby.handle(2, "join 1");
+    let messages = lobby.take_messages();
+    let alice = lines_for(&messages, 1);
+    let bob = lines_for(&messages, 2);
+    assert!(alice.contains(&"created 1".to_string()));
+    assert!(alice.iter().any(|l| l.starts_with("seated 1 0 ")));
+    assert!(bob.iter().any(|l| l.starts_with("start player1 player2")));
+    assert!(alice.last() == Some(&"yourturn".to_string()));
+    assert!(bob.last() == Some(&"turn 0".to_string()));
+
+    // nobody sees another player's hand
+    let state = lobby.tables[&1].state.as_ref().unwrap();
+    let hand = |seat: usize| notation::format_pieces(&state.players[seat].bag);
+    assert!(alice.iter().any(|l| l.starts_with("position") && l.ends_with(&hand(0))));
+    assert!(bob.iter().any(|l| l.starts_with("position") && l.ends_with(&hand(1))));
+
+    lobby.handle(2, "move swap");
+    assert!(lines_for(&lobby.take_messages(), 2) == vec!["error it isn't your turn"]);
+}
+
+#[test]
+fn lobby_should_fill_seats_with_bots_and_let_players_resume() {
+    let mut lobby = Lobby::new("greedy", 4);
+    lobby.connect(1);
+    lobby.handle(1, "create 3");
+    lobby.handle(1, "join 1 2");
+    lobby.handle(1, "start");
+    let messages = lobby.take_messages();
+    let token = messages.iter().find(|m| m.1.starts_with("seated")).unwrap().1[11..].to_string();
+    // the two bots went first
+    assert!(lines_for(&messages, 1).iter().filter(|l| l.starts_with("moved ")).count() == 2);
+    assert!(lines_for(&messages, 1).last() == Some(&"yourturn".to_string()));
+
+    lobby.disconnect(1);
+    lobby.connect(7);
+    lobby.handle(7, "move swap");
+    lobby.handle(7, &format!("resume {}", token));
+    let best = {
+        let state = lobby.tables[&1].state.as_ref().unwrap();
+        notation::format_move(&state.generate_best_move().unwrap())
+    };
+    lobby.handle(7, &format!("move {}", best));
+    let resumed = lines_for(&lobby.take_messages(), 7);
+    assert!(resumed[1] == "error you aren't at a table");
+    assert!(resumed.contains(&format!("seated 1 2 {}", token)));
+    assert!(resumed.iter().any(|l| l.starts_with(&format!("moved 2 ")) && l.ends_with(&best)));
+    assert!(resumed.last() == Some(&"yourturn".to_string()));
+}
+
+#[test]
+fn resume_should_not_hand_out_bot_seats() {
+    let mut lobby = Lobby::new("greedy", 4);
+    lobby.connect(1);
+    lobby.handle(1, "create 2");
+    lobby.handle(1, "join 1");
+    lobby.handle(1, "start");
+    lobby.connect(2);
+    lobby.take_messages();
+    // the bot in seat 1 has no token, so an empty one mustn't match it
+    lobby.handle(2, "resume");
+    lobby.handle(2, "resume  ");
+    let replies = lines_for(&lobby.take_messages(), 2);
+    assert!(replies == vec!["error expected resume <token>", "error expected resume <token>"]);
+    assert!(lobby.clients[&2].seat.is_none());
+}
+
+#[test]
+fn lobby_should_free_seats_once_the_game_is_over() {
+    let mut lobby = Lobby::new("greedy", 6);
+    lobby.connect(1);
+    lobby.handle(1, "create 2 hand=3,copies=1");
+    lobby.handle(1, "join 1");
+    lobby.handle(1, "start");
+    while !lobby.tables[&1].state.as_ref().unwrap().is_over() {
+        let best = {
+            let state = lobby.tables[&1].state.as_ref().unwrap();
+            notation::format_move(&state.generate_best_move().unwrap())
+        };
+        lobby.handle(1, &format!("move {}", best));
+    }
+    assert!(lines_for(&lobby.take_messages(), 1).iter().any(|l| l.starts_with("gameover ")));
+    lobby.handle(1, "create 2");
+    lobby.handle(1, "join 2");
+    let replies = lines_for(&lobby.take_messages(), 1);
+    assert!(replies[0] == "created 2");
+    assert!(replies[1].starts_with("seated 2 0 "));
+}
+
+#[test]
+fn read_lines_should_drop_clients_sending_long_lines() {
+    let input = format!("list\r\n{}\nlist\n", "a".repeat(MAX_LINE + 1));
+    let (events, received) = mpsc::channel();
+    read_lines(1, io::Cursor::new(input.into_bytes()), events);
+    let received: Vec<Event> = received.iter().collect();
+    assert!(received.len() == 2);
+    match (&received[0], &received[1]) {
+        (&Event::Line(1, ref line), &Event::Dropped(1)) => assert!(line == "list"),
+        _ => panic!("expected one line and then a drop"),
+    }
+}
+
+#[test]
+fn host_should_play_over_tcp() {
+    let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+    let port = listener.local_addr().unwrap().port();
+    thread::spawn(move || host(listener, Lobby::new("greedy", 5)));
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut stream = stream;
+    writeln!(stream, "hello ada\ncreate 2\njoin 1\nstart").unwrap();
+    let mut line = String::new();
+    loop {
+        line.clear();
+        assert!(reader.read_line(&mut line).unwrap() > 0);
+        if line.trim() == "yourturn" || line.starts_with("gameover") {
+            break;
+        }
+    }
+}
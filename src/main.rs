@@ -1,33 +1,26 @@
-// Unstable library features:
-#![feature(test)]
-#![feature(convert)]
+extern crate qwirkler;
 
-extern crate rand;
-
-use gamestate::GameState;
-
-mod piece;
-mod board;
-mod gamestate;
-mod direction;
-mod player;
-mod partial;
-mod bench;
-mod linevalidator;
-mod mv;
+use qwirkler::cli;
+use std::io;
+use std::process;
 
 fn main() {
-    let mut game_state = GameState::new(2);
-    loop {
-        match game_state.generate_best_move() {
-            None => break,
-            Some(chosen_move) => {
-                game_state.apply_move(&chosen_move);
-            }
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command = match cli::parse_args(&args) {
+        Ok(command) => command,
+        Err(reason) => {
+            writeln_stderr(&format!("error: {}\n\n{}", reason, cli::USAGE));
+            process::exit(2);
         }
+    };
+    let stdout = io::stdout();
+    if let Err(e) = cli::run(command, &mut stdout.lock()) {
+        writeln_stderr(&format!("error: {}", e));
+        process::exit(1);
     }
+}
 
-    println!("{}", game_state.board);
-    println!("Game finished, total score = {}\n",
-             game_state.total_score());
+fn writeln_stderr(message: &str) {
+    use std::io::Write;
+    let _ = writeln!(io::stderr(), "{}", message);
 }
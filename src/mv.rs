@@ -1,9 +1,183 @@
 use piece::Piece;
 use direction::{Square, Direction};
+use notation;
 use player::Score;
+use std::fmt;
 
-#[derive(Debug)]
+/// A move, either swapping the whole hand or placing pieces in a line from a start square.  A
+/// placement carries how it scores; moves that haven't been through
+/// `GameState::validate_move` (eg ones just parsed) have an empty breakdown.
+#[derive(Debug, Clone)]
 pub enum Move {
     SwapPieces,
-    PlacePieces(Square, Direction, Vec<Piece>, Score),
+    PlacePieces(Square, Direction, Vec<Piece>, ScoreBreakdown),
+}
+
+impl Move {
+    /// The points this move scores (nothing for a swap).
+    pub fn score(&self) -> Score {
+        match *self {
+            Move::SwapPieces => 0,
+            Move::PlacePieces(_, _, _, ref breakdown) => breakdown.total(),
+        }
+    }
+
+    /// The squares this move places pieces on (empty for a swap).
+    pub fn squares(&self) -> Vec<Square> {
+        match *self {
+            Move::SwapPieces => vec![],
+            Move::PlacePieces(sq, ref direction, ref pieces, _) => {
+                direction.apply_all(sq, pieces.len())
+            }
+        }
+    }
+}
+
+/// Written in move notation followed by the score, eg `0,0 R 11 12 (2 = 2 (main))`.
+impl fmt::Display for Move {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Move::SwapPieces => write!(formatter, "{}", notation::format_move(self)),
+            Move::PlacePieces(_, _, _, ref breakdown) => {
+                write!(formatter, "{} ({})", notation::format_move(self), breakdown)
+            }
+        }
+    }
+}
+
+/// One line that a placement formed or made longer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineScore {
+    /// The placed piece the line runs through (the first one, for the main line).
+    pub square: Square,
+    pub length: usize,
+    /// Whether the placement completed the line, which scores six more points.
+    pub qwirkle: bool,
+}
+
+impl LineScore {
+    pub fn points(&self) -> Score {
+        return self.length + if self.qwirkle { 6 } else { 0 };
+    }
+}
+
+/// How a placement's score is made up, line by line.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ScoreBreakdown {
+    /// The line the pieces were placed along.  A single piece that makes perpendicular lines
+    /// scores for those instead, so this is `None`.
+    pub mainline: Option<LineScore>,
+    /// Every perpendicular line the placement made, one per placed piece at most.
+    pub perpendiculars: Vec<LineScore>,
+}
+
+impl ScoreBreakdown {
+    /// Every line that scored, the main line first.
+    pub fn lines(&self) -> Vec<&LineScore> {
+        return self.mainline.iter().chain(self.perpendiculars.iter()).collect();
+    }
+
+    pub fn total(&self) -> Score {
+        return self.lines().iter().fold(0, |acc, line| acc + line.points());
+    }
+
+    /// The six points for every line completed.
+    pub fn qwirkle_bonus(&self) -> Score {
+        return self.lines().iter().filter(|line| line.qwirkle).count() * 6;
+    }
+}
+
+/// Written as a sum, eg `13 = 6 (main) + 3 (perp) + 4 (perp)`, with the lengths of the lines
+/// first and any Qwirkle bonus last: `15 = 6 (main) + 3 (perp) + 6 (qwirkle)`.
+impl fmt::Display for ScoreBreakdown {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}", self.total())?;
+        let main = self.mainline.iter().map(|line| (line, "main"));
+        let perps = self.perpendiculars.iter().map(|line| (line, "perp"));
+        let mut separator = " = ";
+        for (line, kind) in main.chain(perps) {
+            write!(formatter, "{}{} ({})", separator, line.length, kind)?;
+            separator = " + ";
+        }
+        if self.qwirkle_bonus() > 0 {
+            write!(formatter, " + {} (qwirkle)", self.qwirkle_bonus())?;
+        }
+        return Ok(());
+    }
+}
+
+/// The reason a proposed move was rejected by `GameState::validate_move`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum IllegalMove {
+    NoPieces,
+    TooManyPieces,
+    BagEmpty,
+    NotInHand(Piece),
+    OutOfBounds(Square),
+    Occupied(Square),
+    NotConnected,
+    InvalidLine(Square, Piece),
+}
+
+impl fmt::Display for IllegalMove {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            IllegalMove::NoPieces => write!(formatter, "a move must place at least one piece"),
+            IllegalMove::TooManyPieces => write!(formatter, "a line can hold at most six pieces"),
+            IllegalMove::BagEmpty => write!(formatter, "the bag is empty, so you can't swap"),
+            IllegalMove::NotInHand(piece) => {
+                write!(formatter, "{} is not in your hand", piece.to_string())
+            }
+            IllegalMove::OutOfBounds((x, y)) => {
+                write!(formatter, "{},{} is off the edge of the board", x, y)
+            }
+            IllegalMove::Occupied((x, y)) => write!(formatter, "{},{} is already occupied", x, y),
+            IllegalMove::NotConnected => {
+                write!(formatter,
+                       "pieces must touch the existing tiles (or cover 0,0 on the first move)")
+            }
+            IllegalMove::InvalidLine((x, y), piece) => {
+                write!(formatter,
+                       "{} at {},{} doesn't make a valid line",
+                       piece.to_string(),
+                       x,
+                       y)
+            }
+        }
+    }
+}
+
+#[test]
+fn breakdowns_should_be_written_as_sums() {
+    use piece::{Colour, Shape};
+    let line = |square, length| {
+        LineScore {
+            square: square,
+            length: length,
+            qwirkle: length == 6,
+        }
+    };
+    let breakdown = ScoreBreakdown {
+        mainline: Some(line((0, 0), 4)),
+        perpendiculars: vec![line((1, 0), 3)],
+    };
+    assert!(breakdown.to_string() == "7 = 4 (main) + 3 (perp)");
+    let placed = Move::PlacePieces((0, 0), Direction::R, vec![Piece::new(Colour::R, Shape::A)], breakdown);
+    assert!(placed.to_string() == "0,0 R 11 (7 = 4 (main) + 3 (perp))");
+    assert!(placed.score() == 7);
+
+    let qwirkle = ScoreBreakdown {
+        mainline: Some(line((0, 0), 6)),
+        perpendiculars: vec![line((2, 0), 3), line((3, 0), 6)],
+    };
+    assert!(qwirkle.to_string() == "27 = 6 (main) + 3 (perp) + 6 (perp) + 12 (qwirkle)");
+    assert!(qwirkle.total() == 27 && qwirkle.qwirkle_bonus() == 12);
+
+    // a single piece that only scores across its line
+    let across = ScoreBreakdown {
+        mainline: None,
+        perpendiculars: vec![line((0, 1), 2)],
+    };
+    assert!(across.to_string() == "2 = 2 (perp)");
+    assert!(Move::SwapPieces.to_string() == "swap" && Move::SwapPieces.score() == 0);
 }
@@ -0,0 +1,175 @@
+//! Text notation for pieces and moves.
+//!
+//! A piece is written the same way `Piece::to_string` prints it: two digits, colour then shape,
+//! each in the range 1-6 (eg `34` is the fourth shape in the third colour).
+//!
+//! A move is either `swap`, or a start square, a direction and the pieces to place, eg
+//! `0,-1 R 11 12 13`.  Directions are `U` (y + 1), `D` (y - 1), `L` (x - 1) and `R` (x + 1).
+//!
+//! Parsed moves carry an empty `ScoreBreakdown` (so a score of zero) until
+//! `GameState::validate_move` checks them and fills in the lines they make.
+//!
+//! A position lists the pieces on the board as `x,y=piece`, then a `|`, then the hand of the
+//! player to move, eg `0,0=11 1,0=12 | 13 21 35`.
+
+use piece::{Piece, Bag, COLOURS, SHAPES};
+use direction::{Square, Direction};
+use mv::{Move, ScoreBreakdown};
+
+pub fn parse_piece(s: &str) -> Result<Piece, String> {
+    let digits: Vec<char> = s.chars().collect();
+    if digits.len() != 2 {
+        return Err(format!("'{}' is not a piece, expected two digits like 34", s));
+    }
+    let colour = match digits[0].to_digit(10) {
+        Some(d) if d >= 1 && d <= 6 => COLOURS[d as usize - 1],
+        _ => return Err(format!("'{}' has an invalid colour, expected 1-6", s)),
+    };
+    let shape = match digits[1].to_digit(10) {
+        Some(d) if d >= 1 && d <= 6 => SHAPES[d as usize - 1],
+        _ => return Err(format!("'{}' has an invalid shape, expected 1-6", s)),
+    };
+    return Ok(Piece::new(colour, shape));
+}
+
+pub fn parse_square(s: &str) -> Result<Square, String> {
+    let parts: Vec<&str> = s.split(',').collect();
+    if parts.len() != 2 {
+        return Err(format!("'{}' is not a square, expected x,y", s));
+    }
+    match (parts[0].trim().parse::<isize>(), parts[1].trim().parse::<isize>()) {
+        (Ok(x), Ok(y)) => Ok((x, y)),
+        _ => Err(format!("'{}' is not a square, expected x,y", s)),
+    }
+}
+
+pub fn parse_direction(s: &str) -> Result<Direction, String> {
+    match s {
+        "U" | "u" => Ok(Direction::U),
+        "D" | "d" => Ok(Direction::D),
+        "L" | "l" => Ok(Direction::L),
+        "R" | "r" => Ok(Direction::R),
+        _ => Err(format!("'{}' is not a direction, expected U, D, L or R", s)),
+    }
+}
+
+pub fn parse_move(s: &str) -> Result<Move, String> {
+    let tokens: Vec<&str> = s.split_whitespace().collect();
+    if tokens.len() == 1 && tokens[0].to_lowercase() == "swap" {
+        return Ok(Move::SwapPieces);
+    }
+    if tokens.len() < 3 {
+        return Err(format!("'{}' is not a move, expected 'swap' or eg '0,0 R 11 12'",
+                           s.trim()));
+    }
+    let square = parse_square(tokens[0])?;
+    let direction = parse_direction(tokens[1])?;
+    let mut pieces = vec![];
+    for token in &tokens[2..] {
+        pieces.push(parse_piece(token)?);
+    }
+    return Ok(Move::PlacePieces(square, direction, pieces, ScoreBreakdown::default()));
+}
+
+pub fn parse_position(s: &str) -> Result<(Vec<(Square, Piece)>, Bag), String> {
+    let halves: Vec<&str> = s.splitn(2, '|').collect();
+    if halves.len() != 2 {
+        return Err(format!("'{}' is not a position, expected eg '0,0=11 1,0=12 | 13 21'",
+                           s.trim()));
+    }
+    let mut tiles = vec![];
+    for token in halves[0].split_whitespace() {
+        let parts: Vec<&str> = token.splitn(2, '=').collect();
+        if parts.len() != 2 {
+            return Err(format!("'{}' is not a tile, expected eg 0,0=11", token));
+        }
+        tiles.push((parse_square(parts[0])?, parse_piece(parts[1])?));
+    }
+    let mut hand = vec![];
+    for token in halves[1].split_whitespace() {
+        hand.push(parse_piece(token)?);
+    }
+    return Ok((tiles, hand));
+}
+
+pub fn format_direction(direction: &Direction) -> &'static str {
+    match *direction {
+        Direction::U => "U",
+        Direction::D => "D",
+        Direction::L => "L",
+        Direction::R => "R",
+    }
+}
+
+pub fn format_pieces(pieces: &[Piece]) -> String {
+    let strings: Vec<String> = pieces.iter().map(|p| p.to_string()).collect();
+    return strings.join(" ");
+}
+
+pub fn format_position(tiles: &[(Square, Piece)], hand: &[Piece]) -> String {
+    let strings: Vec<String> = tiles.iter()
+                                    .map(|&((x, y), piece)| format!("{},{}={}", x, y, piece.to_string()))
+                                    .collect();
+    return format!("{} | {}", strings.join(" "), format_pieces(hand)).trim().to_string();
+}
+
+pub fn format_move(chosen_move: &Move) -> String {
+    match chosen_move {
+        &Move::SwapPieces => "swap".to_string(),
+        &Move::PlacePieces((x, y), ref direction, ref pieces, _) => {
+            format!("{},{} {} {}",
+                    x,
+                    y,
+                    format_direction(direction),
+                    format_pieces(pieces))
+        }
+    }
+}
+
+#[cfg(test)]
+use piece::{Colour, Shape};
+
+#[test]
+fn parse_piece_should_round_trip() {
+    let piece = Piece::new(Colour::Y, Shape::D);
+    assert!(parse_piece(piece.to_string().as_ref()) == Ok(piece));
+}
+
+#[test]
+fn parse_piece_should_reject_out_of_range_digits() {
+    assert!(parse_piece("07").is_err());
+    assert!(parse_piece("1").is_err());
+    assert!(parse_piece("x1").is_err());
+}
+
+#[test]
+fn parse_move_should_round_trip() {
+    let text = "-2,3 L 11 21 31";
+    let parsed = parse_move(text).unwrap();
+    assert!(format_move(&parsed) == text);
+}
+
+#[test]
+fn parse_move_should_accept_swap() {
+    match parse_move(" swap ") {
+        Ok(Move::SwapPieces) => {}
+        _ => panic!("expected a swap"),
+    }
+}
+
+#[test]
+fn parse_move_should_reject_missing_pieces() {
+    assert!(parse_move("0,0 R").is_err());
+    assert!(parse_move("0,0 X 11").is_err());
+}
+
+#[test]
+fn parse_position_should_read_tiles_and_hand() {
+    let (tiles, hand) = parse_position("0,0=11 1,0=12 | 13 21").unwrap();
+    assert!(tiles == vec![((0, 0), Piece::new(Colour::R, Shape::A)),
+                          ((1, 0), Piece::new(Colour::R, Shape::B))]);
+    assert!(hand == vec![Piece::new(Colour::R, Shape::C), Piece::new(Colour::O, Shape::A)]);
+    assert!(parse_position("| 11").unwrap().0.len() == 0);
+    assert!(parse_position("0,0=11 13").is_err());
+    assert!(format_position(&tiles, &hand) == "0,0=11 1,0=12 | 13 21");
+}
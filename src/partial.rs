@@ -2,15 +2,24 @@ use piece::Piece;
 use linevalidator::LineValidator;
 use player::Score;
 use direction::{Square, Direction};
-use mv::Move;
+use mv::{LineScore, Move, ScoreBreakdown};
 
+/// The most pieces a single move can place: a line never holds more than six.
+pub const MAX_PIECES: usize = 6;
+
+/// A play being built up one piece at a time.  Partials are plain values (the pieces live in a
+/// fixed-size array) so that generating moves doesn't touch the heap.
+#[derive(Copy, Clone)]
 pub struct Partial {
     pub start_square: Square,
     pub direction: Direction,
-    pub pieces: Vec<Piece>,
+    pieces: [Piece; MAX_PIECES],
+    length: usize,
     pub last_square: Square,
     pub mainline_score: Score,
     pub perp_scores: Score,
+    /// What the perpendicular line through each piece scored, so far as `perp_scores` covers.
+    pub perp_line_scores: [Score; MAX_PIECES],
     pub main_validator: Option<LineValidator>,
 }
 
@@ -19,46 +28,89 @@ impl Partial {
         return Partial {
             start_square: square,
             direction: *direction,
-            pieces: vec![piece],
+            pieces: [piece; MAX_PIECES],
+            length: 1,
             mainline_score: 0,
             perp_scores: 0,
+            perp_line_scores: [0; MAX_PIECES],
             last_square: square,
             main_validator: None,
         };
     }
 
+    /// The pieces played so far, in order from the start square.
+    pub fn pieces(&self) -> &[Piece] {
+        return &self.pieces[..self.length];
+    }
+
+    pub fn last_piece(&self) -> Piece {
+        return self.pieces[self.length - 1];
+    }
+
     pub fn total_score(&self) -> Score {
         return self.mainline_score + self.perp_scores;
     }
 
+    /// The lines behind `total_score`.
+    pub fn breakdown(&self) -> ScoreBreakdown {
+        let squares = self.direction.apply_all(self.start_square, self.length);
+        return ScoreBreakdown {
+            mainline: line_score(self.start_square, self.mainline_score),
+            perpendiculars: squares.into_iter()
+                                   .zip(self.perp_line_scores.iter())
+                                   .filter_map(|(sq, &points)| line_score(sq, points))
+                                   .collect(),
+        };
+    }
+
     pub fn save_as_move(&self) -> Move {
         return Move::PlacePieces(self.start_square,
                                  self.direction,
-                                 self.pieces.clone(),
-                                 self.total_score());
+                                 self.pieces().to_vec(),
+                                 self.breakdown());
     }
 
     pub fn try_extend(&self, next_piece: Piece) -> Option<Partial> {
+        if self.length == MAX_PIECES {
+            return None;
+        }
         match self.main_validator {
             None => None,
             Some(ref lv) => {
                 match lv.clone_extend(next_piece) {
                     None => None,
                     Some(lv2) => {
-                        let mut new_pieces = self.pieces.clone();
-                        new_pieces.push(next_piece);
-                        Some(Partial {
-                            start_square: self.start_square,
-                            direction: self.direction,
-                            pieces: new_pieces,
-                            mainline_score: self.mainline_score,
-                            perp_scores: self.perp_scores,
-                            last_square: self.direction.apply(self.last_square),
-                            main_validator: Some(lv2),
-                        })
+                        let mut extended = *self;
+                        extended.pieces[self.length] = next_piece;
+                        extended.length += 1;
+                        extended.last_square = self.direction.apply(self.last_square);
+                        extended.main_validator = Some(lv2);
+                        Some(extended)
                     }
                 }
             }
         }
     }
 }
+
+/// The line through `square` that scored `points`, if it scored at all: a line scores its
+/// length, or twelve once it holds all six pieces.
+fn line_score(square: Square, points: Score) -> Option<LineScore> {
+    match points {
+        0 => None,
+        12 => {
+            Some(LineScore {
+                square: square,
+                length: 6,
+                qwirkle: true,
+            })
+        }
+        length => {
+            Some(LineScore {
+                square: square,
+                length: length,
+                qwirkle: false,
+            })
+        }
+    }
+}
@@ -1,9 +1,16 @@
-use rand::{thread_rng, Rng};
+use rand::Rng;
+#[cfg(test)]
+use rand::thread_rng;
 
 
 pub type Bag = Vec<Piece>;
 
-#[derive(Copy, Clone, PartialEq, Debug, Eq)]
+/// Every colour and shape, in `index()` order.
+pub const COLOURS: [Colour; 6] = [Colour::R, Colour::O, Colour::Y, Colour::G, Colour::B, Colour::P];
+pub const SHAPES: [Shape; 6] = [Shape::A, Shape::B, Shape::C, Shape::D, Shape::E, Shape::F];
+
+#[derive(Copy, Clone, PartialEq, Debug, Eq, Hash)]
+#[repr(u8)]
 pub enum Colour {
     R,
     O,
@@ -15,18 +22,12 @@ pub enum Colour {
 
 impl Colour {
     pub fn index(&self) -> usize {
-        match *self {
-            Colour::R => 0,
-            Colour::O => 1,
-            Colour::Y => 2,
-            Colour::G => 3,
-            Colour::B => 4,
-            Colour::P => 5,
-        }
+        return *self as usize;
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Debug, Eq)]
+#[derive(Copy, Clone, PartialEq, Debug, Eq, Hash)]
+#[repr(u8)]
 pub enum Shape {
     A,
     B,
@@ -38,18 +39,11 @@ pub enum Shape {
 
 impl Shape {
     pub fn index(&self) -> usize {
-        match *self {
-            Shape::A => 0,
-            Shape::B => 1,
-            Shape::C => 2,
-            Shape::D => 3,
-            Shape::E => 4,
-            Shape::F => 5,
-        }
+        return *self as usize;
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Debug, Eq)]
+#[derive(Copy, Clone, PartialEq, Debug, Eq, Hash)]
 pub struct Piece {
     pub colour: Colour,
     pub shape: Shape,
@@ -63,6 +57,18 @@ impl Piece {
         };
     }
 
+    /// A number from 0 to 35 that is different for every kind of piece.  `Colour` and `Shape`
+    /// are one byte each and their discriminants are their indexes, so this is a multiply and
+    /// an add rather than two matches.
+    pub fn index(&self) -> usize {
+        return self.colour as usize * 6 + self.shape as usize;
+    }
+
+    /// The piece whose `index()` is `index`, which must be below 36.
+    pub fn from_index(index: usize) -> Piece {
+        return Piece::new(COLOURS[index / 6], SHAPES[index % 6]);
+    }
+
     pub fn to_string(&self) -> String {
         let mut s = String::new();
         s.push_str((1 + self.colour.index()).to_string().as_ref());
@@ -71,12 +77,17 @@ impl Piece {
     }
 }
 
+#[cfg(test)]
 pub fn make_bag() -> Bag {
-    // this generates three copies of ij for i <- [1..6] and j <- [1..6]
+    return make_bag_with_copies(3);
+}
+
+pub fn make_bag_with_copies(copies: usize) -> Bag {
+    // this generates `copies` copies of ij for i <- [1..6] and j <- [1..6]
     let mut res = vec![];
     for &c in &vec![Colour::R, Colour::O, Colour::Y, Colour::G, Colour::B, Colour::P] {
         for &s in &vec![Shape::A, Shape::B, Shape::C, Shape::D, Shape::E, Shape::F] {
-            for _ in 0..3 {
+            for _ in 0..copies {
                 res.push(Piece::new(c, s));
             }
         }
@@ -84,15 +95,22 @@ pub fn make_bag() -> Bag {
     return res;
 }
 
+#[cfg(test)]
 pub fn resupply_player_mutate(player_bag: &mut Bag, main_bag: &mut Bag) {
-    {
-        let mut rng = thread_rng();
-        let slice = main_bag.as_mut_slice();
-        rng.shuffle(slice);
-    }
-    let num_to_take = 6 - player_bag.len();
-    for _ in 0..num_to_take {
-        main_bag.pop().map(|piece| player_bag.push(piece));
+    resupply_player(player_bag, main_bag, 6, &mut thread_rng());
+}
+
+/// Tops `player_bag` up to `hand_size` pieces, drawing at random from `main_bag` using `rng`.
+pub fn resupply_player<R: Rng>(player_bag: &mut Bag,
+                               main_bag: &mut Bag,
+                               hand_size: usize,
+                               rng: &mut R) {
+    rng.shuffle(main_bag.as_mut_slice());
+    while player_bag.len() < hand_size {
+        match main_bag.pop() {
+            None => break,
+            Some(piece) => player_bag.push(piece),
+        }
     }
 }
 
@@ -147,3 +165,11 @@ fn resupply_should_handle_empties_gracefully() {
     assert!(main_bag.len() == 0);
     assert!(player_bag.len() == 0);
 }
+
+#[test]
+fn index_should_follow_the_colour_and_shape_tables() {
+    for (i, piece) in make_bag_with_copies(1).into_iter().enumerate() {
+        assert!(piece.index() == i && Piece::from_index(i) == piece);
+    }
+    assert!(::std::mem::size_of::<Piece>() == 2);
+}
@@ -0,0 +1,263 @@
+//! Property tests for the rules.
+//!
+//! Each case is a random game, checked before the first move and after every move.  When a
+//! check fails, the game is shrunk to the shortest one that still fails: moves are dropped and
+//! replaced by the first generated move for as long as the failure remains, and the report
+//! lists the moves that are left.
+
+use board::Board;
+use direction::{Direction, Square};
+use gamestate::GameState;
+use linevalidator::is_valid_line;
+use mv::Move;
+use notation;
+use piece::Piece;
+use rand::{Rng, SeedableRng, StdRng};
+use reference;
+use rules::Rules;
+use std::collections::HashSet;
+
+/// How many random games each property is checked on.
+const CASES: usize = 48;
+
+/// A game to check.  The deal comes from `seed`, and `choices[i]` picks the `i`th move from
+/// the generated placements, wrapping around, with one past the last placement meaning swap.
+#[derive(Clone, Debug)]
+struct Case {
+    players: usize,
+    seed: usize,
+    choices: Vec<usize>,
+}
+
+impl Case {
+    fn random(rng: &mut StdRng) -> Case {
+        let length = rng.gen_range(1, 40);
+        return Case {
+            players: rng.gen_range(1, 5),
+            seed: rng.gen(),
+            choices: (0..length).map(|_| rng.gen_range(0, 1000)).collect(),
+        };
+    }
+
+    /// Plays the game, calling `check` on every position until it fails.  On failure, returns
+    /// how many moves had been played and what was wrong.
+    fn run<F: Fn(&GameState) -> Result<(), String>>(&self, check: &F) -> Result<(), (usize, String)> {
+        let mut game_state = GameState::with_rules(self.players, Rules::standard(), self.seed);
+        check(&game_state).map_err(|reason| (0, reason))?;
+        for (i, &choice) in self.choices.iter().enumerate() {
+            if game_state.is_over() {
+                break;
+            }
+            let chosen_move = Case::choose(&game_state, choice);
+            game_state.apply_move(&chosen_move);
+            check(&game_state).map_err(|reason| (i + 1, reason))?;
+        }
+        return Ok(());
+    }
+
+    fn choose(game_state: &GameState, choice: usize) -> Move {
+        let mut moves = game_state.generate_moves();
+        let i = choice % (moves.len() + 1);
+        if i == moves.len() {
+            return Move::SwapPieces;
+        }
+        return moves.swap_remove(i);
+    }
+
+    /// The moves this case plays, in move notation.
+    fn moves(&self) -> Vec<String> {
+        let mut game_state = GameState::with_rules(self.players, Rules::standard(), self.seed);
+        let mut moves = vec![];
+        for &choice in &self.choices {
+            if game_state.is_over() {
+                break;
+            }
+            let chosen_move = Case::choose(&game_state, choice);
+            moves.push(notation::format_move(&chosen_move));
+            game_state.apply_move(&chosen_move);
+        }
+        return moves;
+    }
+}
+
+/// Makes a failing case as small as possible while it keeps failing `check`.
+fn shrink<F: Fn(&GameState) -> Result<(), String>>(mut case: Case, check: &F) -> (Case, String) {
+    let mut failure = case.run(check).unwrap_err();
+    loop {
+        case.choices.truncate(failure.0);
+        let mut smaller = vec![];
+        for i in 0..case.choices.len() {
+            let mut without = case.clone();
+            without.choices.remove(i);
+            smaller.push(without);
+        }
+        for i in 0..case.choices.len() {
+            if case.choices[i] != 0 {
+                let mut simpler = case.clone();
+                simpler.choices[i] = 0;
+                smaller.push(simpler);
+            }
+        }
+        match smaller.into_iter().filter_map(|c| c.run(check).err().map(|f| (c, f))).next() {
+            None => return (case, failure.1),
+            Some((c, f)) => {
+                case = c;
+                failure = f;
+            }
+        }
+    }
+}
+
+/// Checks `check` on `CASES` random games, panicking with the smallest failing game found.
+fn for_all_games<F: Fn(&GameState) -> Result<(), String>>(name: &str, check: F) {
+    let mut rng: StdRng = SeedableRng::from_seed(&[44][..]);
+    for _ in 0..CASES {
+        let case = Case::random(&mut rng);
+        if case.run(&check).is_err() {
+            let (smallest, reason) = shrink(case, &check);
+            panic!("{} failed: {}\n{} players, seed {}, moves:\n{}",
+                   name,
+                   reason,
+                   smallest.players,
+                   smallest.seed,
+                   smallest.moves().join("\n"));
+        }
+    }
+}
+
+/// The run of pieces through `sq` along `direction` (`sq` must hold a piece).
+fn line_through(board: &Board, sq: Square, direction: Direction) -> Vec<Piece> {
+    let mut start = sq;
+    while board.get(direction.opposite().apply(start)).is_some() {
+        start = direction.opposite().apply(start);
+    }
+    let mut line = vec![];
+    while let Some(piece) = board.get(start) {
+        line.push(piece);
+        start = direction.apply(start);
+    }
+    return line;
+}
+
+fn lines_are_valid(game_state: &GameState) -> Result<(), String> {
+    let board = &game_state.board;
+    for ((x, y), _) in board.tiles() {
+        for &direction in &[Direction::R, Direction::U] {
+            let line = line_through(board, (x, y), direction);
+            if !is_valid_line(&line) {
+                return Err(format!("the line through {},{} is {}",
+                                   x,
+                                   y,
+                                   notation::format_pieces(&line)));
+            }
+        }
+    }
+    return Ok(());
+}
+
+fn perimeter_is_next_to_the_tiles(game_state: &GameState) -> Result<(), String> {
+    let board = &game_state.board;
+    let mut expected = HashSet::new();
+    for (sq, _) in board.tiles() {
+        for d in &Direction::all() {
+            if board.get(d.apply(sq)).is_none() {
+                expected.insert(d.apply(sq));
+            }
+        }
+    }
+    if expected != *board.perimeter() {
+        return Err(format!("the perimeter should be {:?} but is {:?}", expected, board.perimeter()));
+    }
+    return Ok(());
+}
+
+fn scores_can_be_recomputed(game_state: &GameState) -> Result<(), String> {
+    let mut board = Board::new();
+    let mut scores = vec![0; game_state.players.len()];
+    for (i, chosen_move) in game_state.history.iter().enumerate() {
+        if let Move::PlacePieces(sq, direction, ref pieces, _) = *chosen_move {
+            let score = chosen_move.score();
+            let expected = reference::score(&board, sq, direction, pieces);
+            if expected != Some(score) {
+                return Err(format!("move {} ({}) scored {} rather than {:?}",
+                                   i + 1,
+                                   notation::format_move(chosen_move),
+                                   score,
+                                   expected));
+            }
+            board.put(sq, &direction, pieces);
+            scores[i % game_state.players.len()] += score;
+        }
+    }
+    let actual: Vec<_> = game_state.players.iter().map(|p| p.score).collect();
+    if actual != scores || board.tiles() != game_state.board.tiles() {
+        return Err(format!("the scores are {:?} but the history adds up to {:?}", actual, scores));
+    }
+    return Ok(());
+}
+
+fn pieces_add_up(game_state: &GameState) -> Result<(), String> {
+    let mut counts = [0; 36];
+    let board_pieces = game_state.board.tiles().into_iter().map(|(_, p)| p);
+    for piece in board_pieces.chain(game_state.players[0].bag.iter().cloned())
+                             .chain(game_state.unseen_pieces(0).into_iter()) {
+        counts[piece.index()] += 1;
+    }
+    let total = counts.iter().fold(0, |acc, &n| acc + n);
+    if total != 108 || counts.iter().any(|&n| n != 3) {
+        return Err(format!("there are {} pieces: {:?}", total, &counts[..]));
+    }
+    return Ok(());
+}
+
+#[test]
+fn every_line_on_the_board_should_be_valid() {
+    for_all_games("every line on the board is valid", lines_are_valid);
+}
+
+#[test]
+fn the_perimeter_should_be_the_empty_squares_next_to_tiles() {
+    for_all_games("the perimeter is the empty squares next to tiles",
+                  perimeter_is_next_to_the_tiles);
+}
+
+#[test]
+fn scores_should_be_recomputable_from_the_moves() {
+    for_all_games("scores can be recomputed from the moves", scores_can_be_recomputed);
+}
+
+#[test]
+fn there_should_always_be_108_pieces() {
+    for_all_games("there are always 108 pieces", pieces_add_up);
+}
+
+#[test]
+fn games_should_stay_consistent() {
+    for_all_games("the game is consistent", |game_state| {
+        match game_state.check_invariants().into_iter().next() {
+            None => Ok(()),
+            Some(violation) => Err(violation),
+        }
+    });
+}
+
+#[test]
+fn shrink_should_find_the_smallest_failing_game() {
+    let check = |game_state: &GameState| {
+        if game_state.board.tiles().len() < 2 {
+            Ok(())
+        } else {
+            Err("two pieces have been played".to_string())
+        }
+    };
+    let case = Case {
+        players: 2,
+        seed: 1,
+        choices: vec![5, 9, 17, 3, 8, 1, 4],
+    };
+    assert!(case.run(&check).is_err());
+    let (smallest, reason) = shrink(case, &check);
+    assert!(reason == "two pieces have been played");
+    assert!(smallest.choices.len() <= 2);
+    assert!(smallest.run(&check).is_err());
+}
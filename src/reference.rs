@@ -0,0 +1,283 @@
+//! A slow but straightforward move generator, for checking the fast one in `gamestate.rs`.
+//!
+//! Nothing here shares code with `visit_partials`, `LineValidator` or the board's cross-checks
+//! (`is_valid_line` just compares the pieces):
+//! every start square and direction near the tiles is tried with every ordering of every
+//! selection of pieces from the hand, and each placement is checked and scored by looking at
+//! the lines it makes.  Like `generate_moves`, placements are runs of adjacent empty squares.
+
+use board::Board;
+use direction::{Direction, Square};
+use linevalidator::is_valid_line;
+use mv::{LineScore, Move, ScoreBreakdown};
+use notation;
+use piece::Piece;
+use player::Score;
+use std::collections::{HashMap, HashSet};
+
+/// The squares and pieces of a placement, in a fixed order, however the move was written.
+pub type Placement = Vec<(Square, usize)>;
+
+pub fn placement(chosen_move: &Move) -> Placement {
+    let mut placed = match *chosen_move {
+        Move::SwapPieces => vec![],
+        Move::PlacePieces(_, _, ref pieces, _) => {
+            chosen_move.squares().into_iter().zip(pieces.iter().map(|p| p.index())).collect()
+        }
+    };
+    placed.sort();
+    return placed;
+}
+
+fn piece_at(board: &Board, placed: &[(Square, Piece)], sq: Square) -> Option<Piece> {
+    return placed.iter().find(|&&(s, _)| s == sq).map(|&(_, p)| p).or_else(|| board.get(sq));
+}
+
+/// Every piece in the line through `sq` along `direction`, once `placed` is on the board.
+fn line_through(board: &Board, placed: &[(Square, Piece)], sq: Square, direction: Direction) -> Vec<Piece> {
+    let mut line: Vec<Piece> = piece_at(board, placed, sq).into_iter().collect();
+    for &d in &[direction, direction.opposite()] {
+        let mut next = d.apply(sq);
+        while let Some(piece) = piece_at(board, placed, next) {
+            line.push(piece);
+            next = d.apply(next);
+        }
+    }
+    return line;
+}
+
+/// Checks and scores placing `pieces` from `start` along `direction`, or returns `None` if the
+/// rules don't allow it.
+pub fn score(board: &Board, start: Square, direction: Direction, pieces: &[Piece]) -> Option<Score> {
+    return breakdown(board, start, direction, pieces).map(|b| b.total());
+}
+
+/// Like `score`, split into the lines the placement makes.
+pub fn breakdown(board: &Board,
+                 start: Square,
+                 direction: Direction,
+                 pieces: &[Piece])
+                 -> Option<ScoreBreakdown> {
+    return score_on(board, board.tiles().is_empty(), start, direction, pieces);
+}
+
+fn score_on(board: &Board,
+            board_is_empty: bool,
+            start: Square,
+            direction: Direction,
+            pieces: &[Piece])
+            -> Option<ScoreBreakdown> {
+    // the start is checked first, as stepping along from near the ends of isize would overflow
+    if pieces.is_empty() || pieces.len() > 6 || !board.in_bounds(start) {
+        return None;
+    }
+    let squares = direction.apply_all(start, pieces.len());
+    if squares.iter().any(|&sq| !board.in_bounds(sq) || board.get(sq).is_some()) {
+        return None;
+    }
+    let connected = if board_is_empty {
+        squares.contains(&(0, 0))
+    } else {
+        squares.iter().any(|&sq| Direction::all().iter().any(|d| board.get(d.apply(sq)).is_some()))
+    };
+    if !connected {
+        return None;
+    }
+
+    // every line with a new piece in it: the one the pieces were placed along, and the one
+    // across it through each of them
+    let placed: Vec<(Square, Piece)> = squares.iter().cloned().zip(pieces.iter().cloned()).collect();
+    let (across, _) = direction.perpendiculars();
+    let mut lines = vec![line_through(board, &placed, start, direction)];
+    for &sq in &squares {
+        lines.push(line_through(board, &placed, sq, across));
+    }
+    if !lines.iter().all(|line| is_valid_line(line)) {
+        return None;
+    }
+
+    // a line scores a point per piece, and six more if it is complete; a piece on its own
+    // (only possible on the first move) scores one
+    let line_score = |sq, line: &Vec<Piece>| {
+        LineScore {
+            square: sq,
+            length: line.len(),
+            qwirkle: line.len() == 6,
+        }
+    };
+    let perpendiculars: Vec<LineScore> = squares.iter()
+                                                .zip(&lines[1..])
+                                                .filter(|&(_, line)| line.len() > 1)
+                                                .map(|(&sq, line)| line_score(sq, line))
+                                                .collect();
+    let mainline = if lines[0].len() > 1 || perpendiculars.is_empty() {
+        Some(line_score(start, &lines[0]))
+    } else {
+        None
+    };
+    return Some(ScoreBreakdown {
+        mainline: mainline,
+        perpendiculars: perpendiculars,
+    });
+}
+
+/// Calls `visit` with every ordering of every selection of up to `room` of `pieces` that could
+/// form a line by themselves (any legal placement's pieces share a line, so nothing else can
+/// be legal).
+fn for_each_line<F: FnMut(&[Piece])>(pieces: &[Piece], room: usize, line: &mut Vec<Piece>, visit: &mut F) {
+    if line.len() == room {
+        return;
+    }
+    for &piece in pieces {
+        line.push(piece);
+        if is_valid_line(line) {
+            visit(line);
+            for_each_line(pieces, room, line, visit);
+        }
+        line.pop();
+    }
+}
+
+/// Every legal placement of pieces from `hand`, each listed once.
+pub fn all_placements(board: &Board, hand: &[Piece]) -> Vec<Move> {
+    let mut pieces: Vec<Piece> = vec![];
+    for &piece in hand {
+        if !pieces.contains(&piece) {
+            pieces.push(piece);
+        }
+    }
+    let board_is_empty = board.tiles().is_empty();
+
+    // a placement has to touch the tiles (or cover 0,0), so it starts at most six squares
+    // before the bounding box, and no further than one square outside it
+    let ((min_x, min_y), (max_x, max_y)) = board.bounding_box();
+    let mut starts = vec![];
+    for y in min_y - 1..max_y + 2 {
+        for x in min_x - 6..max_x + 2 {
+            starts.push(((x, y), Direction::R));
+        }
+    }
+    for x in min_x - 1..max_x + 2 {
+        for y in min_y - 6..max_y + 2 {
+            starts.push(((x, y), Direction::U));
+        }
+    }
+
+    let mut seen: HashSet<Placement> = HashSet::new();
+    let mut moves = vec![];
+    for &(start, direction) in &starts {
+        if !board.in_bounds(start) {
+            continue;
+        }
+        // the empty squares in a row from the start
+        let room = direction.apply_all(start, 6)
+                            .iter()
+                            .take_while(|&&sq| board.in_bounds(sq) && board.get(sq).is_none())
+                            .count();
+        for_each_line(&pieces, room, &mut vec![], &mut |line| {
+            if let Some(breakdown) = score_on(board, board_is_empty, start, direction, line) {
+                let found = Move::PlacePieces(start, direction, line.to_vec(), breakdown);
+                if seen.insert(placement(&found)) {
+                    moves.push(found);
+                }
+            }
+        });
+    }
+    return moves;
+}
+
+/// Compares `GameState::generate_moves` (given as `generated`) with `all_placements`, and
+/// describes every placement that is missing, extra, listed twice or scored differently, line
+/// by line.
+pub fn differences(board: &Board, hand: &[Piece], generated: &[Move]) -> Vec<String> {
+    let breakdown_of = |m: &Move| {
+        match *m {
+            Move::PlacePieces(_, _, _, ref breakdown) => breakdown.clone(),
+            Move::SwapPieces => ScoreBreakdown::default(),
+        }
+    };
+    let mut expected: HashMap<Placement, &Move> = HashMap::new();
+    let reference = all_placements(board, hand);
+    for m in &reference {
+        expected.insert(placement(m), m);
+    }
+
+    let mut problems = vec![];
+    let mut seen: HashSet<Placement> = HashSet::new();
+    for m in generated {
+        let key = placement(m);
+        if !seen.insert(key.clone()) {
+            problems.push(format!("{} was generated twice", notation::format_move(m)));
+        }
+        match expected.get(&key) {
+            None => problems.push(format!("{} is not legal", notation::format_move(m))),
+            Some(&e) if breakdown_of(e) != breakdown_of(m) => {
+                problems.push(format!("{} scores {}, not {}",
+                                      notation::format_move(m),
+                                      breakdown_of(e),
+                                      breakdown_of(m)))
+            }
+            Some(_) => {}
+        }
+    }
+    for m in &reference {
+        if !seen.contains(&placement(m)) {
+            problems.push(format!("{} (scoring {}) was not generated", notation::format_move(m), m.score()));
+        }
+    }
+    return problems;
+}
+
+#[cfg(test)]
+fn compare_over_self_play(games: usize) {
+    use gamestate::GameState;
+    use rules::Rules;
+    use strategy::{RandomMoves, Strategy};
+    let mut positions = 0;
+    for seed in 0..games {
+        let mut game_state = GameState::with_rules(2, Rules::standard(), seed);
+        let mut strategy = RandomMoves::new(seed);
+        while !game_state.is_over() {
+            let hand = &game_state.players[game_state.turn].bag;
+            let problems = differences(&game_state.board, hand, &game_state.generate_moves());
+            assert!(problems.is_empty(),
+                    "in position {} (seed {}):\n{}",
+                    notation::format_position(&game_state.board.tiles(), hand),
+                    seed,
+                    problems.join("\n"));
+            positions += 1;
+            let chosen_move = strategy.choose_move(&game_state);
+            game_state.apply_move(&chosen_move);
+        }
+    }
+    assert!(positions >= games * 10);
+}
+
+#[test]
+fn score_should_follow_the_rules() {
+    use piece::{Colour, Shape};
+    let a = Piece::new(Colour::R, Shape::A);
+    let b = Piece::new(Colour::R, Shape::B);
+    let c = Piece::new(Colour::O, Shape::B);
+    let mut board = Board::new();
+    assert!(score(&board, (0, 0), Direction::R, &[a]) == Some(1));
+    assert!(score(&board, (1, 0), Direction::R, &[a]) == None);
+    assert!(score(&board, (0, 0), Direction::R, &[a, a]) == None);
+    board.put((0, 0), &Direction::R, &vec![a, b]);
+    assert!(score(&board, (1, 1), Direction::U, &[c]) == Some(2));
+    assert!(score(&board, (2, 0), Direction::R, &[c]) == None);
+    assert!(score(&board, (3, 0), Direction::R, &[c]) == None);
+    assert!(score(&board, (isize::MAX, 0), Direction::R, &[c, a]) == None);
+}
+
+#[test]
+fn generate_moves_should_match_the_reference_generator() {
+    compare_over_self_play(2);
+}
+
+/// Thousands of positions; run with `cargo test --release -- --ignored`.
+#[test]
+#[ignore]
+fn generate_moves_should_match_the_reference_generator_at_length() {
+    compare_over_self_play(100);
+}
@@ -0,0 +1,190 @@
+use board::Board;
+use dead::DeadSpots;
+use direction::Square;
+use piece::{Piece, Colour, Shape};
+use std::env;
+use std::io::{self, IsTerminal};
+
+/// How `render` should draw pieces.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum RenderMode {
+    /// Two digit piece notation, safe for pipes and files.
+    Plain,
+    /// Unicode shape glyphs in ANSI colours.
+    Colour,
+}
+
+impl RenderMode {
+    /// Colour when stdout is a terminal (and `NO_COLOR` isn't set), plain otherwise.
+    pub fn auto() -> RenderMode {
+        if io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none() {
+            return RenderMode::Colour;
+        }
+        return RenderMode::Plain;
+    }
+
+    /// Parses the `--colour` option: `auto`, `always` or `never`.
+    pub fn parse(s: &str) -> Result<RenderMode, String> {
+        match s {
+            "auto" => Ok(RenderMode::auto()),
+            "always" => Ok(RenderMode::Colour),
+            "never" => Ok(RenderMode::Plain),
+            _ => Err(format!("--colour expects auto, always or never, got '{}'", s)),
+        }
+    }
+}
+
+/// What the board renderers draw on top of the pieces.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Overlay {
+    Nothing,
+    /// The squares that can never be filled and the lines that can never be completed (see
+    /// `DeadSpots`).
+    Dead,
+}
+
+impl Overlay {
+    /// Parses the `--overlay` option: `none` or `dead`.
+    pub fn parse(s: &str) -> Result<Overlay, String> {
+        match s {
+            "none" => Ok(Overlay::Nothing),
+            "dead" => Ok(Overlay::Dead),
+            _ => Err(format!("--overlay expects none or dead, got '{}'", s)),
+        }
+    }
+
+    /// The dead spots to draw for `board` in a game with `copies` of every piece, if any.
+    pub fn dead_spots(&self, board: &Board, copies: usize) -> DeadSpots {
+        match *self {
+            Overlay::Nothing => DeadSpots::default(),
+            Overlay::Dead => DeadSpots::find(board, copies),
+        }
+    }
+}
+
+const RESET: &'static str = "\x1b[0m";
+const HIGHLIGHT: &'static str = "\x1b[7m";
+const DIM: &'static str = "\x1b[2m";
+
+pub fn glyph(shape: Shape) -> char {
+    match shape {
+        Shape::A => '●',
+        Shape::B => '■',
+        Shape::C => '◆',
+        Shape::D => '♣',
+        Shape::E => '★',
+        Shape::F => '✚',
+    }
+}
+
+pub fn ansi_colour(colour: Colour) -> &'static str {
+    match colour {
+        Colour::R => "\x1b[31m",
+        Colour::O => "\x1b[38;5;208m",
+        Colour::Y => "\x1b[33m",
+        Colour::G => "\x1b[32m",
+        Colour::B => "\x1b[34m",
+        Colour::P => "\x1b[35m",
+    }
+}
+
+fn render_piece(piece: Piece, highlighted: bool, dead: bool, mode: RenderMode) -> String {
+    match mode {
+        RenderMode::Plain => {
+            let mark = match (highlighted, dead) {
+                (true, _) => "*",
+                (false, true) => "~",
+                (false, false) => " ",
+            };
+            format!("{}{}", piece.to_string(), mark)
+        }
+        RenderMode::Colour => {
+            format!(" {}{}{}{}{} ",
+                    ansi_colour(piece.colour),
+                    if highlighted { HIGHLIGHT } else { "" },
+                    if dead { DIM } else { "" },
+                    glyph(piece.shape),
+                    RESET)
+        }
+    }
+}
+
+/// Draws the board with x coordinates along the top and y coordinates down the side.
+/// Pieces on any of the `highlight` squares (eg the last move) are marked.
+#[cfg(test)]
+pub fn render(board: &Board, highlight: &[Square], mode: RenderMode) -> String {
+    return render_with_dead(board, highlight, &DeadSpots::default(), mode);
+}
+
+/// Like `render`, also marking the squares and lines in `dead`: dead squares are drawn as
+/// `xx` (or a dim `✕`), and pieces in dead lines are followed by `~` (or dimmed) unless
+/// they are highlighted.
+pub fn render_with_dead(board: &Board, highlight: &[Square], dead: &DeadSpots, mode: RenderMode) -> String {
+    let ((min_x, min_y), (max_x, max_y)) = board.bounding_box();
+    let mut output = String::new();
+
+    output.push_str("    ");
+    for x in min_x - 1..max_x + 2 {
+        output.push_str(&format!("{:>2} ", x));
+    }
+    output.push_str("\n");
+
+    for y in min_y - 1..max_y + 2 {
+        output.push_str(&format!("{:>3} ", y));
+        for x in min_x - 1..max_x + 2 {
+            match board.get((x, y)) {
+                None if dead.is_dead_square((x, y)) => {
+                    match mode {
+                        RenderMode::Plain => output.push_str("xx "),
+                        RenderMode::Colour => output.push_str(&format!(" {}✕{} ", DIM, RESET)),
+                    }
+                }
+                None if mode == RenderMode::Colour => output.push_str(" · "),
+                None => output.push_str(".. "),
+                Some(p) => {
+                    output.push_str(&render_piece(p,
+                                                  highlight.contains(&(x, y)),
+                                                  dead.in_dead_line((x, y)),
+                                                  mode))
+                }
+            }
+        }
+        output.push_str("\n");
+    }
+    return output;
+}
+
+#[cfg(test)]
+use dead;
+#[cfg(test)]
+use direction::Direction;
+
+#[test]
+fn render_should_label_axes_and_mark_highlighted_pieces() {
+    let mut board = Board::new();
+    board.put((0, 0),
+              &Direction::R,
+              &vec![Piece::new(Colour::R, Shape::A), Piece::new(Colour::R, Shape::B)]);
+    let plain = render(&board, &[(1, 0)], RenderMode::Plain);
+    assert!(plain.lines().next() == Some("    -1  0  1  2 "));
+    assert!(plain.contains("  0 .. 11 12*.. "));
+
+    let colour = render(&board, &[(1, 0)], RenderMode::Colour);
+    assert!(colour.contains("\x1b[31m●\x1b[0m"));
+    assert!(colour.contains("\x1b[31m\x1b[7m■\x1b[0m"));
+}
+
+#[test]
+fn render_with_dead_should_mark_dead_squares_and_lines() {
+    let board = dead::every_red_piece();
+    let dead = Overlay::Dead.dead_spots(&board, 1);
+    let plain = render_with_dead(&board, &[(0, 1)], &dead, RenderMode::Plain);
+    assert!(plain.contains("  0 xx 11~12~13~14~15~xx "));
+    assert!(plain.contains("  1 .. 16*xx .. "));
+
+    let colour = render_with_dead(&board, &[], &dead, RenderMode::Colour);
+    assert!(colour.contains("\x1b[31m\x1b[2m●\x1b[0m"));
+    assert!(colour.contains(" \x1b[2m✕\x1b[0m "));
+    assert!(Overlay::Nothing.dead_spots(&board, 1) == DeadSpots::default());
+    assert!(Overlay::parse("dead") == Ok(Overlay::Dead) && Overlay::parse("all").is_err());
+}
@@ -0,0 +1,70 @@
+use std::fmt;
+
+/// Rule variants that can be chosen when a game is created.
+///
+/// The standard game uses three copies of each of the 36 pieces and a hand of six.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Rules {
+    pub hand_size: usize,
+    pub copies: usize,
+}
+
+impl Rules {
+    pub fn standard() -> Rules {
+        return Rules {
+            hand_size: 6,
+            copies: 3,
+        };
+    }
+
+    /// Parses either `standard` or a comma separated list of overrides, eg `hand=4,copies=2`.
+    pub fn parse(s: &str) -> Result<Rules, String> {
+        let mut rules = Rules::standard();
+        if s == "standard" {
+            return Ok(rules);
+        }
+        for setting in s.split(',') {
+            let parts: Vec<&str> = setting.splitn(2, '=').collect();
+            if parts.len() != 2 {
+                return Err(format!("'{}' is not a rule, expected eg hand=6 or copies=3",
+                                   setting));
+            }
+            let value = match parts[1].parse::<usize>() {
+                Ok(v) => v,
+                Err(_) => return Err(format!("'{}' is not a number", parts[1])),
+            };
+            match parts[0] {
+                "hand" if value >= 1 && value <= 12 => rules.hand_size = value,
+                "hand" => return Err("hand size must be between 1 and 12".to_string()),
+                "copies" if value >= 1 && value <= 6 => rules.copies = value,
+                "copies" => return Err("copies must be between 1 and 6".to_string()),
+                _ => return Err(format!("unknown rule '{}', expected hand or copies", parts[0])),
+            }
+        }
+        return Ok(rules);
+    }
+}
+
+impl fmt::Display for Rules {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        if *self == Rules::standard() {
+            return write!(formatter, "standard");
+        }
+        write!(formatter, "hand={},copies={}", self.hand_size, self.copies)
+    }
+}
+
+#[test]
+fn parse_should_round_trip() {
+    let rules = Rules::parse("copies=2,hand=4").unwrap();
+    assert!(rules.hand_size == 4 && rules.copies == 2);
+    assert!(Rules::parse(rules.to_string().as_ref()) == Ok(rules));
+    assert!(Rules::parse("standard") == Ok(Rules::standard()));
+}
+
+#[test]
+fn parse_should_reject_unknown_and_out_of_range_rules() {
+    assert!(Rules::parse("hand=0").is_err());
+    assert!(Rules::parse("colours=5").is_err());
+    assert!(Rules::parse("hand").is_err());
+}
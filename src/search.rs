@@ -0,0 +1,132 @@
+use gamestate::{self, GameState};
+use mv::Move;
+use player::Score;
+use rand::{Rng, SeedableRng, StdRng};
+use strategy::Strategy;
+use std::time::{Duration, Instant};
+
+/// What the last call to `Search::choose_move` did.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SearchStats {
+    pub depth: usize,
+    /// Positions scored, counting each candidate and each simulated reply.
+    pub nodes: usize,
+    /// Opponent hands sampled per candidate.
+    pub samples: usize,
+    /// Expected score of the chosen move, after subtracting the expected reply.
+    pub value: f64,
+    pub elapsed: Duration,
+}
+
+/// A two ply search: the best few placements are each scored against the greedy reply of an
+/// opponent holding a random hand drawn from the pieces this player hasn't seen.  Hands keep
+/// being sampled until `movetime` runs out, and the move with the best score minus the average
+/// reply is played.  At least one hand is sampled per candidate, however short `movetime` is.
+pub struct Search {
+    pub movetime: Duration,
+    /// How many of the highest scoring placements are considered.
+    pub width: usize,
+    pub stats: SearchStats,
+    rng: StdRng,
+}
+
+impl Search {
+    pub fn new(movetime: Duration, seed: usize) -> Search {
+        return Search {
+            movetime: movetime,
+            width: 8,
+            stats: SearchStats::default(),
+            rng: SeedableRng::from_seed(&[seed][..]),
+        };
+    }
+}
+
+impl Strategy for Search {
+    fn name(&self) -> &str {
+        return "search";
+    }
+
+    fn choose_move(&mut self, game_state: &GameState) -> Move {
+        let start = Instant::now();
+        let mut candidates = game_state.generate_moves();
+        if candidates.len() == 0 {
+            self.stats = SearchStats::default();
+            return Move::SwapPieces;
+        }
+        candidates.sort_by(|a, b| b.score().cmp(&a.score()));
+        candidates.truncate(self.width);
+
+        let boards: Vec<_> = candidates.iter()
+                                       .map(|candidate| {
+                                           let mut board = game_state.board.clone();
+                                           if let Move::PlacePieces(sq, ref dir, ref pieces, _) =
+                                                  *candidate {
+                                               board.put(sq, dir, pieces);
+                                           }
+                                           board
+                                       })
+                                       .collect();
+        let mut unseen = game_state.unseen_pieces(game_state.turn);
+        let hand_size = if game_state.rules.hand_size < unseen.len() {
+            game_state.rules.hand_size
+        } else {
+            unseen.len()
+        };
+
+        let mut replies: Vec<Score> = vec![0; candidates.len()];
+        let mut samples = 0;
+        let mut nodes = candidates.len();
+        loop {
+            self.rng.shuffle(&mut unseen);
+            let opponent_hand = &unseen[..hand_size];
+            for (i, board) in boards.iter().enumerate() {
+                replies[i] += gamestate::best_placement(board, opponent_hand)
+                                  .map_or(0, |reply| reply.score());
+                nodes += 1;
+            }
+            samples += 1;
+            if start.elapsed() >= self.movetime || hand_size == 0 {
+                break;
+            }
+        }
+
+        let values: Vec<f64> = candidates.iter()
+                                         .zip(replies.iter())
+                                         .map(|(candidate, &reply)| {
+                                             candidate.score() as f64 -
+                                             reply as f64 / samples as f64
+                                         })
+                                         .collect();
+        let mut best = 0;
+        for i in 1..values.len() {
+            if values[i] > values[best] {
+                best = i;
+            }
+        }
+        self.stats = SearchStats {
+            depth: 2,
+            nodes: nodes,
+            samples: samples,
+            value: values[best],
+            elapsed: start.elapsed(),
+        };
+        return candidates.swap_remove(best);
+    }
+}
+
+#[cfg(test)]
+use rules::Rules;
+
+#[test]
+fn search_should_play_legal_moves_and_report_stats() {
+    let mut game_state = GameState::with_rules(2, Rules::standard(), 11);
+    let mut search = Search::new(Duration::from_millis(0), 11);
+    for _ in 0..6 {
+        let chosen_move = search.choose_move(&game_state);
+        if let Move::PlacePieces(..) = chosen_move {
+            assert!(game_state.validate_move(&chosen_move).is_ok());
+            assert!(search.stats.samples >= 1 && search.stats.nodes > search.stats.samples);
+        }
+        game_state.apply_move(&chosen_move);
+    }
+}
@@ -0,0 +1,495 @@
+use gamestate::GameState;
+use json::Json;
+use mv::{Move, ScoreBreakdown};
+use notation;
+use rules::Rules;
+use strategy::{self, Strategy};
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::panic::{self, AssertUnwindSafe};
+use std::time::Duration;
+
+/// An in-memory HTTP/JSON game server, for driving the engine from a web page or a script.
+///
+/// Routes (every response body is JSON, errors look like `{"error": "..."}`):
+///
+/// * `POST /games` with `{"seats": ["human", "greedy"], "rules": "standard", "seed": 1}`
+///   creates a game (every field is optional) and returns `{"id": 1}`.
+/// * `GET /games/<id>?player=<n>` returns the game as seen by player `n`.
+/// * `POST /games/<id>/move` with `{"player": 0, "move": "0,0 R 11 12"}` plays a move and
+///   returns the mover's new view.  Bot seats then play until it's a human's turn again.
+/// * `GET /games/<id>/hint?player=<n>` returns the greedy bot's choice,
+///   `{"move", "score", "breakdown"}` with the breakdown listing every line that scored.
+/// * `GET /games/<id>/moves?player=<n>` lists every legal placement as `{"moves": [...]}`.
+///
+/// Players are numbered from zero.  Requests are handled one at a time on a single thread, so
+/// bodies over `MAX_BODY` bytes are refused with a 413 and connections that go quiet for
+/// `TIMEOUT` are dropped.
+pub struct Server {
+    games: HashMap<usize, ServerGame>,
+    next_id: usize,
+}
+
+struct ServerGame {
+    state: GameState,
+    /// `None` marks a human seat.
+    bots: Vec<Option<Box<dyn Strategy>>>,
+}
+
+impl ServerGame {
+    fn play_bots(&mut self) {
+        while !self.state.is_over() {
+            let chosen_move = match self.bots[self.state.turn] {
+                None => return,
+                Some(ref mut strategy) => strategy.choose_move(&self.state),
+            };
+            self.state.apply_move(&chosen_move);
+        }
+    }
+}
+
+/// The largest request body the server will read.
+pub const MAX_BODY: usize = 64 * 1024;
+
+/// The longest request or header line the server will read, and how many headers it accepts.
+pub const MAX_HEADER: usize = 8 * 1024;
+const MAX_HEADERS: usize = 100;
+
+/// How long a connection may take to send its request or read the response.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct Response {
+    pub status: u16,
+    pub body: Json,
+}
+
+fn ok(body: Json) -> Response {
+    return Response {
+        status: 200,
+        body: body,
+    };
+}
+
+fn error(status: u16, message: &str) -> Response {
+    return Response {
+        status: status,
+        body: Json::object(vec![("error", Json::str(message))]),
+    };
+}
+
+/// A move as `{"move": "<notation>", "score": <n>, "breakdown": [...]}` (see
+/// `breakdown_json`), without the breakdown for a swap.
+pub fn move_json(chosen_move: &Move) -> Json {
+    let mut fields = vec![("move", Json::Str(notation::format_move(chosen_move))),
+                          ("score", Json::Int(chosen_move.score() as i64))];
+    if let Move::PlacePieces(_, _, _, ref breakdown) = *chosen_move {
+        fields.push(("breakdown", breakdown_json(breakdown)));
+    }
+    return Json::object(fields);
+}
+
+/// Every line a placement scored for, main line first, as
+/// `{"kind": "main" or "perp", "x", "y", "length", "qwirkle", "points"}` where `x,y` is the
+/// placed piece the line runs through.
+pub fn breakdown_json(breakdown: &ScoreBreakdown) -> Json {
+    let main = breakdown.mainline.iter().map(|line| ("main", line));
+    let perps = breakdown.perpendiculars.iter().map(|line| ("perp", line));
+    let lines = main.chain(perps)
+                    .map(|(kind, line)| {
+                        let (x, y) = line.square;
+                        Json::object(vec![("kind", Json::str(kind)),
+                                          ("x", Json::Int(x as i64)),
+                                          ("y", Json::Int(y as i64)),
+                                          ("length", Json::Int(line.length as i64)),
+                                          ("qwirkle", Json::Bool(line.qwirkle)),
+                                          ("points", Json::Int(line.points() as i64))])
+                    })
+                    .collect();
+    return Json::Array(lines);
+}
+
+/// Reads `player=<n>` out of a query string.
+fn player_param(query: &str) -> Option<usize> {
+    return query.split('&')
+                .filter_map(|pair| {
+                    let parts: Vec<&str> = pair.splitn(2, '=').collect();
+                    if parts.len() == 2 && parts[0] == "player" {
+                        parts[1].parse().ok()
+                    } else {
+                        None
+                    }
+                })
+                .next();
+}
+
+impl Server {
+    pub fn new() -> Server {
+        return Server {
+            games: HashMap::new(),
+            next_id: 1,
+        };
+    }
+
+    /// Routes one request.  `path` may include a query string.
+    pub fn handle(&mut self, method: &str, path: &str, body: &str) -> Response {
+        let (path, query) = match path.find('?') {
+            None => (path, ""),
+            Some(i) => (&path[..i], &path[i + 1..]),
+        };
+        let segments: Vec<&str> = path.split('/').filter(|s| s.len() > 0).collect();
+        match (method, segments.as_slice()) {
+            ("POST", ["games"]) => self.create_game(body),
+            (method, ["games", id, rest @ ..]) => {
+                let id = match id.parse::<usize>() {
+                    Ok(id) => id,
+                    Err(_) => return error(404, "no such game"),
+                };
+                let game = match self.games.get_mut(&id) {
+                    None => return error(404, "no such game"),
+                    Some(game) => game,
+                };
+                match (method, rest) {
+                    ("POST", ["move"]) => submit_move(game, body),
+                    ("GET", []) | ("GET", ["hint"]) | ("GET", ["moves"]) => {
+                        let player = match player_param(query) {
+                            Some(p) if p < game.state.players.len() => p,
+                            _ => return error(400, "expected ?player=<n> for a seat in this game"),
+                        };
+                        match rest {
+                            [] => ok(game.state.view(player).to_json()),
+                            _ if player != game.state.turn || game.state.is_over() => {
+                                error(409, "it isn't this player's turn")
+                            }
+                            ["hint"] => ok(move_json(&strategy::Greedy.choose_move(&game.state))),
+                            _ => {
+                                let moves = game.state.generate_moves().iter().map(move_json).collect();
+                                ok(Json::object(vec![("moves", Json::Array(moves))]))
+                            }
+                        }
+                    }
+                    _ => error(404, "no such route"),
+                }
+            }
+            _ => error(404, "no such route"),
+        }
+    }
+
+    fn create_game(&mut self, body: &str) -> Response {
+        let request = if body.trim().len() == 0 {
+            Json::Object(vec![])
+        } else {
+            match Json::parse(body) {
+                Ok(request) => request,
+                Err(reason) => return error(400, &format!("bad JSON: {}", reason)),
+            }
+        };
+        let rules = match request.get("rules").map(|r| r.as_str()) {
+            None => Rules::standard(),
+            Some(Some(spec)) => {
+                match Rules::parse(spec) {
+                    Ok(rules) => rules,
+                    Err(reason) => return error(400, &reason),
+                }
+            }
+            Some(None) => return error(400, "rules must be a string"),
+        };
+        let seed = match request.get("seed").map(|s| s.as_i64()) {
+            None => self.next_id,
+            Some(Some(seed)) if seed >= 0 => seed as usize,
+            Some(_) => return error(400, "seed must be a non-negative integer"),
+        };
+        let names: Vec<String> = match request.get("seats") {
+            None => vec!["human".to_string(), "greedy".to_string()],
+            Some(seats) => {
+                match seats.as_array() {
+                    Some(seats) if seats.iter().all(|s| s.as_str().is_some()) => {
+                        seats.iter().map(|s| s.as_str().unwrap().to_string()).collect()
+                    }
+                    _ => return error(400, "seats must be an array of strategy names"),
+                }
+            }
+        };
+        if names.len() == 0 || names.len() > 6 || names.len() * rules.hand_size > 36 * rules.copies {
+            return error(400, "unsupported number of seats");
+        }
+        let mut bots = vec![];
+        for (seat, name) in names.iter().enumerate() {
+            if name == "human" {
+                bots.push(None);
+                continue;
+            }
+            match strategy::from_name(name, seed.wrapping_add(seat + 1)) {
+                Ok(strategy) => bots.push(Some(strategy)),
+                Err(reason) => return error(400, &reason),
+            }
+        }
+
+        let mut game = ServerGame {
+            state: GameState::with_rules(names.len(), rules, seed),
+            bots: bots,
+        };
+        game.play_bots();
+        let id = self.next_id;
+        self.next_id += 1;
+        self.games.insert(id, game);
+        return ok(Json::object(vec![("id", Json::Int(id as i64))]));
+    }
+}
+
+fn submit_move(game: &mut ServerGame, body: &str) -> Response {
+    let request = match Json::parse(body) {
+        Ok(request) => request,
+        Err(reason) => return error(400, &format!("bad JSON: {}", reason)),
+    };
+    let player = match request.get("player").and_then(|p| p.as_i64()) {
+        Some(p) if p >= 0 && (p as usize) < game.state.players.len() => p as usize,
+        _ => return error(400, "expected \"player\": <n> for a seat in this game"),
+    };
+    if player != game.state.turn || game.state.is_over() {
+        return error(409, "it isn't this player's turn");
+    }
+    let proposed = match request.get("move").and_then(|m| m.as_str()).map(notation::parse_move) {
+        None => return error(400, "expected \"move\": \"<notation>\""),
+        Some(Err(reason)) => return error(400, &reason),
+        Some(Ok(proposed)) => proposed,
+    };
+    match game.state.validate_move(&proposed) {
+        Err(reason) => error(400, &reason.to_string()),
+        Ok(legal) => {
+            game.state.apply_move(&legal);
+            game.play_bots();
+            ok(game.state.view(player).to_json())
+        }
+    }
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        204 => "No Content",
+        400 => "Bad Request",
+        404 => "Not Found",
+        409 => "Conflict",
+        413 => "Payload Too Large",
+        431 => "Request Header Fields Too Large",
+        _ => "Internal Server Error",
+    }
+}
+
+/// Why `read_request` couldn't read a request.
+pub enum RequestError {
+    /// The connection failed or timed out, so there is no one to answer.
+    Io(io::Error),
+    /// The request was malformed or too large, and gets this response.
+    Rejected(Response),
+}
+
+impl From<io::Error> for RequestError {
+    fn from(e: io::Error) -> RequestError {
+        return RequestError::Io(e);
+    }
+}
+
+/// Reads one line of the request line or headers, refusing any longer than `MAX_HEADER`.
+fn read_header_line<R: BufRead>(reader: &mut R) -> Result<String, RequestError> {
+    let mut line = String::new();
+    reader.by_ref().take(MAX_HEADER as u64 + 1).read_line(&mut line)?;
+    if line.len() > MAX_HEADER {
+        return Err(RequestError::Rejected(error(431,
+                                                &format!("header lines are limited to {} bytes",
+                                                         MAX_HEADER))));
+    }
+    return Ok(line);
+}
+
+/// Reads one HTTP/1.1 request, returning the method, path and body.
+pub fn read_request<R: BufRead>(reader: &mut R) -> Result<(String, String, String), RequestError> {
+    let request_line = read_header_line(reader)?;
+    let parts: Vec<&str> = request_line.split_whitespace().collect();
+    if parts.len() < 2 {
+        return Err(RequestError::Rejected(error(400, "malformed request line")));
+    }
+
+    let mut content_length = 0;
+    let mut headers = 0;
+    loop {
+        let header = read_header_line(reader)?;
+        if header.trim().len() == 0 {
+            break;
+        }
+        headers += 1;
+        if headers > MAX_HEADERS {
+            return Err(RequestError::Rejected(error(431,
+                                                    &format!("requests are limited to {} headers",
+                                                             MAX_HEADERS))));
+        }
+        let fields: Vec<&str> = header.splitn(2, ':').collect();
+        if fields.len() == 2 && fields[0].trim().eq_ignore_ascii_case("content-length") {
+            content_length = match fields[1].trim().parse() {
+                Ok(n) => n,
+                Err(_) => return Err(RequestError::Rejected(error(400, "bad content-length"))),
+            };
+        }
+    }
+    if content_length > MAX_BODY {
+        return Err(RequestError::Rejected(error(413,
+                                                &format!("bodies are limited to {} bytes", MAX_BODY))));
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+    let body = String::from_utf8_lossy(&body).into_owned();
+    return Ok((parts[0].to_string(), parts[1].to_string(), body));
+}
+
+/// Runs `handle`, turning a panic (a bug in the engine) into a 500 so that the server keeps
+/// serving other requests.
+fn catch_panics<F: FnOnce() -> Response>(handle: F) -> Response {
+    return panic::catch_unwind(AssertUnwindSafe(handle)).unwrap_or_else(|_| error(500, "internal error"));
+}
+
+fn handle_connection(server: &mut Server, stream: TcpStream) -> io::Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let (status, text) = match read_request(&mut reader) {
+        Ok((ref method, _, _)) if method == "OPTIONS" => (204, String::new()),
+        Ok((method, path, body)) => {
+            let response = catch_panics(|| server.handle(&method, &path, &body));
+            (response.status, response.body.to_string())
+        }
+        Err(RequestError::Rejected(response)) => (response.status, response.body.to_string()),
+        Err(RequestError::Io(e)) => return Err(e),
+    };
+    let mut stream = stream;
+    write!(stream,
+           "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+            Access-Control-Allow-Origin: *\r\nAccess-Control-Allow-Methods: GET, POST, OPTIONS\r\n\
+            Access-Control-Allow-Headers: Content-Type\r\nConnection: close\r\n\r\n{}",
+           status,
+           reason_phrase(status),
+           text.len(),
+           text)?;
+    return stream.flush();
+}
+
+/// Serves games on `127.0.0.1:<port>` until the process is killed.
+pub fn serve(port: u16) -> io::Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    let mut server = Server::new();
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                if let Err(e) = handle_connection(&mut server, stream) {
+                    let _ = writeln!(io::stderr(), "request failed: {}", e);
+                }
+            }
+            Err(e) => {
+                let _ = writeln!(io::stderr(), "connection failed: {}", e);
+            }
+        }
+    }
+    return Ok(());
+}
+
+#[test]
+fn server_should_play_a_game_through_the_api() {
+    let mut server = Server::new();
+    let created = server.handle("POST", "/games", r#"{"seats": ["human", "greedy"], "seed": 3}"#);
+    assert!(created.status == 200);
+    let id = created.body.get("id").and_then(|id| id.as_i64()).unwrap();
+
+    let view = server.handle("GET", &format!("/games/{}?player=0", id), "").body;
+    assert!(view.get("hand").and_then(|h| h.as_array()).map(|h| h.len()) == Some(6));
+    assert!(view.get("turn") == Some(&Json::Int(0)));
+
+    let hint = server.handle("GET", &format!("/games/{}/hint?player=0", id), "").body;
+    let moves = server.handle("GET", &format!("/games/{}/moves?player=0", id), "").body;
+    assert!(moves.get("moves").and_then(|m| m.as_array()).map(|m| m.len() > 0) == Some(true));
+
+    let body = Json::object(vec![("player", Json::Int(0)), ("move", hint.get("move").unwrap().clone())]);
+    let played = server.handle("POST", &format!("/games/{}/move", id), &body.to_string());
+    assert!(played.status == 200);
+    // the bot has replied, so it's the human's turn again
+    assert!(played.body.get("turn") == Some(&Json::Int(0)));
+    assert!(played.body.get("board").and_then(|b| b.as_array()).map(|b| b.len() > 0) == Some(true));
+}
+
+#[test]
+fn server_should_reject_bad_requests() {
+    let mut server = Server::new();
+    assert!(server.handle("GET", "/games/7?player=0", "").status == 404);
+    assert!(server.handle("POST", "/games", r#"{"seats": ["wizard"]}"#).status == 400);
+    assert!(server.handle("POST", "/games", "{").status == 400);
+    server.handle("POST", "/games", "");
+    assert!(server.handle("GET", "/games/1", "").status == 400);
+    assert!(server.handle("GET", "/games/1/hint?player=1", "").status == 409);
+    let illegal = server.handle("POST", "/games/1/move", r#"{"player": 0, "move": "9,9 R 11"}"#);
+    assert!(illegal.status == 400);
+    assert!(illegal.body.get("error").is_some());
+}
+
+#[test]
+fn read_request_should_parse_the_body() {
+    let raw = "POST /games HTTP/1.1\r\nHost: localhost\r\nContent-Length: 2\r\n\r\n{}";
+    match read_request(&mut io::Cursor::new(raw.as_bytes())) {
+        Ok((method, path, body)) => assert!(method == "POST" && path == "/games" && body == "{}"),
+        _ => panic!("expected a request"),
+    }
+}
+
+#[test]
+fn read_request_should_refuse_large_bodies() {
+    // without the limit this would allocate 10GB before finding that the body is missing
+    let raw = "POST /games HTTP/1.1\r\nContent-Length: 10000000000\r\n\r\n";
+    match read_request(&mut io::Cursor::new(raw.as_bytes())) {
+        Err(RequestError::Rejected(response)) => assert!(response.status == 413),
+        _ => panic!("expected a 413"),
+    }
+}
+
+#[test]
+fn read_request_should_refuse_long_or_many_headers() {
+    let long = format!("GET /games/1 HTTP/1.1\r\nX-Padding: {}\r\n\r\n", "a".repeat(MAX_HEADER));
+    let many = format!("GET /games/1 HTTP/1.1\r\n{}\r\n", "X-Padding: a\r\n".repeat(MAX_HEADERS + 1));
+    let endless = format!("GET /{}", "a".repeat(10 * MAX_HEADER));
+    for raw in &[long, many, endless] {
+        match read_request(&mut io::Cursor::new(raw.as_bytes())) {
+            Err(RequestError::Rejected(response)) => assert!(response.status == 431),
+            _ => panic!("expected a 431"),
+        }
+    }
+}
+
+#[test]
+fn catch_panics_should_answer_500() {
+    assert!(catch_panics(|| panic!("a bug")).status == 500);
+    assert!(catch_panics(|| ok(Json::Null)).status == 200);
+}
+
+#[test]
+fn move_json_should_list_the_breakdown_line_by_line() {
+    use direction::Direction;
+    use mv::LineScore;
+    use piece::{Colour, Piece, Shape};
+    let line = |square, length| {
+        LineScore {
+            square: square,
+            length: length,
+            qwirkle: length == 6,
+        }
+    };
+    let breakdown = ScoreBreakdown {
+        mainline: Some(line((0, 0), 6)),
+        perpendiculars: vec![line((1, 0), 2)],
+    };
+    let placed = Move::PlacePieces((0, 0), Direction::R, vec![Piece::new(Colour::R, Shape::A)], breakdown);
+    let json = move_json(&placed);
+    assert!(json.get("score") == Some(&Json::Int(14)));
+    let lines = json.get("breakdown").and_then(|b| b.as_array()).unwrap();
+    assert!(lines.len() == 2);
+    assert!(lines[0].to_string() == r#"{"kind":"main","x":0,"y":0,"length":6,"qwirkle":true,"points":12}"#);
+    assert!(lines[1].get("kind") == Some(&Json::str("perp")) && lines[1].get("points") == Some(&Json::Int(2)));
+    assert!(move_json(&Move::SwapPieces).get("breakdown").is_none());
+}
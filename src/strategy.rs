@@ -0,0 +1,116 @@
+use gamestate::{GameState, TieBreak};
+use mv::Move;
+use rand::{Rng, SeedableRng, StdRng};
+use external::{ExternalEngine, IllegalReply};
+use search::Search;
+use std::time::Duration;
+
+/// A way of choosing moves for the player whose turn it is.
+///
+/// Strategies are only asked for a move while the game is still running (see
+/// `GameState::is_over`), so there is always at least a swap or a placement available.
+pub trait Strategy {
+    fn name(&self) -> &str;
+    fn choose_move(&mut self, game_state: &GameState) -> Move;
+
+    /// True once the strategy has given up on the game, eg an external engine that broke the
+    /// protocol.  Tournaments count a forfeited game as a loss whatever the score.
+    fn forfeited(&self) -> bool {
+        return false;
+    }
+}
+
+/// Plays the highest scoring move available, swapping only when nothing can be placed.
+pub struct Greedy;
+
+impl Strategy for Greedy {
+    fn name(&self) -> &str {
+        return "greedy";
+    }
+
+    fn choose_move(&mut self, game_state: &GameState) -> Move {
+        return game_state.generate_best_move().unwrap_or(Move::SwapPieces);
+    }
+}
+
+/// Like `Greedy`, but chooses between equally scoring placements with `tie_break`.  On the
+/// command line this is `greedy:<policy>`, eg `greedy:longer`.
+pub struct GreedyTieBreak {
+    name: String,
+    tie_break: TieBreak,
+}
+
+impl GreedyTieBreak {
+    /// `policy` is one of `gamestate::TIE_BREAKS`; `random` draws from `seed`.
+    pub fn new(policy: &str, seed: usize) -> Result<GreedyTieBreak, String> {
+        return Ok(GreedyTieBreak {
+            name: format!("greedy:{}", policy),
+            tie_break: TieBreak::parse(policy, seed)?,
+        });
+    }
+}
+
+impl Strategy for GreedyTieBreak {
+    fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    fn choose_move(&mut self, game_state: &GameState) -> Move {
+        return game_state.choose_best_move(self.tie_break).unwrap_or(Move::SwapPieces);
+    }
+}
+
+/// Plays a uniformly random legal placement, swapping only when nothing can be placed.
+pub struct RandomMoves {
+    rng: StdRng,
+}
+
+impl RandomMoves {
+    pub fn new(seed: usize) -> RandomMoves {
+        return RandomMoves { rng: SeedableRng::from_seed(&[seed][..]) };
+    }
+}
+
+impl Strategy for RandomMoves {
+    fn name(&self) -> &str {
+        return "random";
+    }
+
+    fn choose_move(&mut self, game_state: &GameState) -> Move {
+        let mut moves = game_state.generate_moves();
+        if moves.len() == 0 {
+            return Move::SwapPieces;
+        }
+        let i = self.rng.gen_range(0, moves.len());
+        return moves.swap_remove(i);
+    }
+}
+
+pub const NAMES: &'static [&'static str] = &["greedy", "random", "search"];
+
+/// Looks up a strategy by the name used on the command line.  As well as `NAMES`, a name can be
+/// `greedy:<policy>`, greedy with a tie-break policy from `gamestate::TIE_BREAKS`,
+/// `external:<command>`, for another program speaking the engine protocol whose illegal moves
+/// become swaps, or `external-strict:<command>`, where an illegal move forfeits the game.
+pub fn from_name(name: &str, seed: usize) -> Result<Box<dyn Strategy>, String> {
+    if name.starts_with("greedy:") {
+        return Ok(Box::new(GreedyTieBreak::new(&name["greedy:".len()..], seed)?));
+    }
+    if name.starts_with("external:") {
+        return Ok(Box::new(ExternalEngine::new(&name["external:".len()..], IllegalReply::Swap)));
+    }
+    if name.starts_with("external-strict:") {
+        return Ok(Box::new(ExternalEngine::new(&name["external-strict:".len()..],
+                                               IllegalReply::Forfeit)));
+    }
+    match name {
+        "greedy" => Ok(Box::new(Greedy)),
+        "random" => Ok(Box::new(RandomMoves::new(seed))),
+        "search" => Ok(Box::new(Search::new(Duration::from_millis(100), seed))),
+        _ => {
+            Err(format!("unknown strategy '{}', expected one of: {}",
+                        name,
+                        NAMES.join(", ")))
+        }
+    }
+}
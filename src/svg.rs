@@ -0,0 +1,248 @@
+use board::Board;
+use dead::DeadSpots;
+use direction::Square;
+use mv::Move;
+use piece::{Piece, Colour, Shape};
+use render::Overlay;
+use std::f64::consts::PI;
+
+/// Size of one square in SVG user units.
+const CELL: f64 = 40.0;
+
+pub fn fill_colour(colour: Colour) -> &'static str {
+    match colour {
+        Colour::R => "#d62728",
+        Colour::O => "#ff7f0e",
+        Colour::Y => "#f2c80f",
+        Colour::G => "#2ca02c",
+        Colour::B => "#1f77b4",
+        Colour::P => "#9467bd",
+    }
+}
+
+/// Formats coordinates as the value of an SVG `points` attribute.
+fn points(coords: &[(f64, f64)]) -> String {
+    let strings: Vec<String> = coords.iter().map(|&(x, y)| format!("{:.1},{:.1}", x, y)).collect();
+    return strings.join(" ");
+}
+
+/// Draws one shape, filling roughly the middle 70% of the square whose centre is `(cx, cy)`.
+fn shape_svg(piece: Piece, cx: f64, cy: f64) -> String {
+    let r = CELL * 0.35;
+    let fill = fill_colour(piece.colour);
+    match piece.shape {
+        Shape::A => format!("<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{:.1}\" fill=\"{}\"/>", cx, cy, r, fill),
+        Shape::B => {
+            let side = r * 1.6;
+            format!("<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\"/>",
+                    cx - side / 2.0,
+                    cy - side / 2.0,
+                    side,
+                    side,
+                    fill)
+        }
+        Shape::C => {
+            format!("<polygon points=\"{}\" fill=\"{}\"/>",
+                    points(&[(cx, cy - r), (cx + r, cy), (cx, cy + r), (cx - r, cy)]),
+                    fill)
+        }
+        Shape::D => {
+            let leaf = r * 0.5;
+            let mut circles = String::new();
+            for &(dx, dy) in &[(0.0, -1.0), (1.0, 0.0), (0.0, 1.0), (-1.0, 0.0)] {
+                circles.push_str(&format!("<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{:.1}\" fill=\"{}\"/>",
+                                          cx + dx * leaf,
+                                          cy + dy * leaf,
+                                          leaf,
+                                          fill));
+            }
+            circles
+        }
+        Shape::E => {
+            let coords: Vec<(f64, f64)> = (0..16)
+                                              .map(|i| {
+                                                  let radius = if i % 2 == 0 { r } else { r * 0.45 };
+                                                  let angle = PI * i as f64 / 8.0 - PI / 2.0;
+                                                  (cx + radius * angle.cos(), cy + radius * angle.sin())
+                                              })
+                                              .collect();
+            format!("<polygon points=\"{}\" fill=\"{}\"/>", points(&coords), fill)
+        }
+        Shape::F => {
+            let w = r * 0.35;
+            format!("<polygon points=\"{}\" fill=\"{}\"/>",
+                    points(&[(cx - w, cy - r),
+                             (cx + w, cy - r),
+                             (cx + w, cy - w),
+                             (cx + r, cy - w),
+                             (cx + r, cy + w),
+                             (cx + w, cy + w),
+                             (cx + w, cy + r),
+                             (cx - w, cy + r),
+                             (cx - w, cy + w),
+                             (cx - r, cy + w),
+                             (cx - r, cy - w),
+                             (cx - w, cy - w)]),
+                    fill)
+        }
+    }
+}
+
+/// Renders the board as a standalone SVG document, with x coordinates along the top and
+/// y coordinates down the side.  Pieces on the `highlight` squares get a gold outline.
+#[cfg(test)]
+pub fn board_to_svg(board: &Board, highlight: &[Square]) -> String {
+    return board_to_svg_with_dead(board, highlight, &DeadSpots::default());
+}
+
+/// Like `board_to_svg`, also crossing out the squares in `dead` and striking a dashed red line
+/// through each of its lines.
+pub fn board_to_svg_with_dead(board: &Board, highlight: &[Square], dead: &DeadSpots) -> String {
+    let ((min_x, min_y), (max_x, max_y)) = board.bounding_box();
+    // one square of margin around the pieces, plus one for the axis labels
+    let columns = (max_x - min_x + 3) as f64;
+    let rows = (max_y - min_y + 3) as f64;
+    let left = |x: isize| (x - min_x + 2) as f64 * CELL;
+    let top = |y: isize| (y - min_y + 2) as f64 * CELL;
+
+    let mut svg = format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" \
+                           viewBox=\"0 0 {0} {1}\">\n",
+                          (columns + 1.0) * CELL,
+                          (rows + 1.0) * CELL);
+    svg.push_str("<rect width=\"100%\" height=\"100%\" fill=\"#f4f1e8\"/>\n");
+    svg.push_str("<g font-family=\"sans-serif\" font-size=\"14\" fill=\"#555\" \
+                  text-anchor=\"middle\">\n");
+    for x in min_x - 1..max_x + 2 {
+        svg.push_str(&format!("<text x=\"{:.1}\" y=\"{:.1}\">{}</text>\n",
+                              left(x) + CELL / 2.0,
+                              CELL * 0.65,
+                              x));
+    }
+    for y in min_y - 1..max_y + 2 {
+        svg.push_str(&format!("<text x=\"{:.1}\" y=\"{:.1}\">{}</text>\n",
+                              CELL / 2.0,
+                              top(y) + CELL * 0.6,
+                              y));
+    }
+    svg.push_str("</g>\n");
+
+    for y in min_y - 1..max_y + 2 {
+        for x in min_x - 1..max_x + 2 {
+            match board.get((x, y)) {
+                None => {
+                    svg.push_str(&format!("<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" \
+                                           height=\"{:.1}\" fill=\"none\" stroke=\"#ddd8c8\"/>\n",
+                                          left(x),
+                                          top(y),
+                                          CELL,
+                                          CELL));
+                    if dead.is_dead_square((x, y)) {
+                        let (x0, y0) = (left(x) + 8.0, top(y) + 8.0);
+                        let (x1, y1) = (left(x) + CELL - 8.0, top(y) + CELL - 8.0);
+                        svg.push_str(&format!("<path d=\"M{:.1},{:.1} L{:.1},{:.1} M{:.1},{:.1} L{:.1},{:.1}\" \
+                                               stroke=\"#b3aa96\" stroke-width=\"3\"/>\n",
+                                              x0,
+                                              y0,
+                                              x1,
+                                              y1,
+                                              x0,
+                                              y1,
+                                              x1,
+                                              y0));
+                    }
+                }
+                Some(piece) => {
+                    let stroke = if highlight.contains(&(x, y)) {
+                        " stroke=\"#ffd700\" stroke-width=\"3\""
+                    } else {
+                        ""
+                    };
+                    svg.push_str(&format!("<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" \
+                                           height=\"{:.1}\" rx=\"4\" fill=\"#222\"{}/>\n",
+                                          left(x) + 1.0,
+                                          top(y) + 1.0,
+                                          CELL - 2.0,
+                                          CELL - 2.0,
+                                          stroke));
+                    svg.push_str(&shape_svg(piece, left(x) + CELL / 2.0, top(y) + CELL / 2.0));
+                    svg.push_str("\n");
+                }
+            }
+        }
+    }
+    for line in &dead.lines {
+        let ((x0, y0), (x1, y1)) = (line.start, *line.squares().last().unwrap());
+        svg.push_str(&format!("<line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" \
+                               stroke=\"#c0392b\" stroke-width=\"3\" stroke-dasharray=\"6,4\"/>\n",
+                              left(x0) + CELL / 2.0,
+                              top(y0) + CELL / 2.0,
+                              left(x1) + CELL / 2.0,
+                              top(y1) + CELL / 2.0));
+    }
+    svg.push_str("</svg>\n");
+    return svg;
+}
+
+/// Replays a game record onto an empty board and renders one SVG frame after every move,
+/// highlighting the pieces that move placed.  Swaps produce a frame with nothing highlighted.
+/// `overlay` is drawn on every frame, for a game with `copies` of every piece.
+pub fn replay_to_svg_frames(history: &[Move], overlay: Overlay, copies: usize) -> Vec<String> {
+    let mut board = Board::new();
+    let mut frames = vec![];
+    for chosen_move in history {
+        if let Move::PlacePieces(sq, ref direction, ref pieces, _) = *chosen_move {
+            board.put(sq, direction, pieces);
+        }
+        frames.push(board_to_svg_with_dead(&board,
+                                           &chosen_move.squares(),
+                                           &overlay.dead_spots(&board, copies)));
+    }
+    return frames;
+}
+
+#[cfg(test)]
+use dead;
+#[cfg(test)]
+use direction::Direction;
+#[cfg(test)]
+use mv::ScoreBreakdown;
+
+#[test]
+fn board_to_svg_should_draw_labels_pieces_and_highlights() {
+    let mut board = Board::new();
+    board.put((0, 0),
+              &Direction::R,
+              &vec![Piece::new(Colour::R, Shape::A), Piece::new(Colour::G, Shape::A)]);
+    let svg = board_to_svg(&board, &[(1, 0)]);
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+    assert!(svg.ends_with("</svg>\n"));
+    assert!(svg.contains(">-1</text>") && svg.contains(">2</text>"));
+    assert!(svg.contains("fill=\"#d62728\"") && svg.contains("fill=\"#2ca02c\""));
+    assert!(svg.matches("stroke=\"#ffd700\"").count() == 1);
+}
+
+#[test]
+fn replay_to_svg_frames_should_produce_one_frame_per_turn() {
+    let history = vec![Move::PlacePieces((0, 0),
+                                         Direction::R,
+                                         vec![Piece::new(Colour::R, Shape::A)],
+                                         ScoreBreakdown::default()),
+                       Move::SwapPieces,
+                       Move::PlacePieces((1, 0),
+                                         Direction::R,
+                                         vec![Piece::new(Colour::R, Shape::B)],
+                                         ScoreBreakdown::default())];
+    let frames = replay_to_svg_frames(&history, Overlay::Nothing, 3);
+    assert!(frames.len() == 3);
+    assert!(frames[1].matches("stroke=\"#ffd700\"").count() == 0);
+    assert!(frames[2].matches("fill=\"#222\"").count() == 2);
+}
+
+#[test]
+fn board_to_svg_with_dead_should_cross_out_dead_squares_and_strike_dead_lines() {
+    let board = dead::every_red_piece();
+    let svg = board_to_svg_with_dead(&board, &[], &DeadSpots::find(&board, 1));
+    assert!(svg.matches("stroke=\"#b3aa96\"").count() == 5);
+    assert!(svg.matches("stroke-dasharray").count() == 2);
+    assert!(!board_to_svg(&board, &[]).contains("stroke-dasharray"));
+}
@@ -0,0 +1,49 @@
+use direction::Square;
+use json::Json;
+use notation;
+use piece::{Bag, Piece};
+use player::Score;
+use rules::Rules;
+
+/// Everything one player is allowed to know about a game: the board, their own hand, the scores
+/// and how many pieces are left in the bag, but not the other players' hands.
+#[derive(Clone, Debug)]
+pub struct PlayerView {
+    pub player: usize,
+    pub turn: usize,
+    pub over: bool,
+    pub rules: Rules,
+    pub tiles: Vec<(Square, Piece)>,
+    pub hand: Bag,
+    pub scores: Vec<Score>,
+    pub bag_size: usize,
+}
+
+impl PlayerView {
+    /// The board and hand in position notation, eg `0,0=11 1,0=12 | 13 21 35`.
+    pub fn position(&self) -> String {
+        return notation::format_position(&self.tiles, &self.hand);
+    }
+
+    pub(crate) fn to_json(&self) -> Json {
+        let board = self.tiles
+                        .iter()
+                        .map(|&((x, y), piece)| {
+                            Json::object(vec![("x", Json::Int(x as i64)),
+                                              ("y", Json::Int(y as i64)),
+                                              ("piece", Json::Str(piece.to_string()))])
+                        })
+                        .collect();
+        return Json::object(vec![("player", Json::Int(self.player as i64)),
+                                 ("turn", Json::Int(self.turn as i64)),
+                                 ("over", Json::Bool(self.over)),
+                                 ("rules", Json::Str(self.rules.to_string())),
+                                 ("scores",
+                                  Json::Array(self.scores.iter().map(|&s| Json::Int(s as i64)).collect())),
+                                 ("bag", Json::Int(self.bag_size as i64)),
+                                 ("hand",
+                                  Json::Array(self.hand.iter().map(|p| Json::Str(p.to_string())).collect())),
+                                 ("board", Json::Array(board)),
+                                 ("position", Json::Str(self.position()))]);
+    }
+}
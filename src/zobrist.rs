@@ -0,0 +1,64 @@
+//! Zobrist keys for hashing positions.
+//!
+//! A position's hash is the XOR of one key for every feature it has (a piece on a square, a
+//! piece in a hand, whose turn it is), so it can be updated one feature at a time as the
+//! position changes.  Rather than storing a table of random keys, each key is derived by
+//! mixing the feature's coordinates with splitmix64, which gives the same keys in every run.
+
+use direction::Square;
+use piece::Piece;
+
+/// Who is holding a piece: a player, or the bag.
+pub const BAG: usize = 255;
+
+fn mix(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    return z ^ (z >> 31);
+}
+
+/// The key for `piece` lying on `square`.
+pub fn square_key((x, y): Square, piece: Piece) -> u64 {
+    let x = (x as u64) & 0xffff;
+    let y = (y as u64) & 0xffff;
+    return mix(1 << 56 | x << 24 | y << 8 | piece.index() as u64);
+}
+
+/// The key for the `copy`th copy (counting from 0) of `piece` held by `owner`, a player number
+/// or `BAG`.  Counting copies keeps two identical pieces from cancelling each other out.
+pub fn holding_key(owner: usize, piece: Piece, copy: usize) -> u64 {
+    return mix(2 << 56 | (owner as u64 & 0xff) << 24 | (copy as u64 & 0xff) << 8 |
+               piece.index() as u64);
+}
+
+/// The key for it being `player`'s turn.
+pub fn turn_key(player: usize) -> u64 {
+    return mix(3 << 56 | player as u64);
+}
+
+/// The keys for a collection of pieces held by `owner`, combined.
+pub fn holding_hash(owner: usize, pieces: &[Piece]) -> u64 {
+    let mut counts = [0; 36];
+    let mut hash = 0;
+    for piece in pieces {
+        hash ^= holding_key(owner, *piece, counts[piece.index()]);
+        counts[piece.index()] += 1;
+    }
+    return hash;
+}
+
+#[cfg(test)]
+use piece::{Colour, Shape};
+
+#[test]
+fn holding_hash_should_ignore_order_but_count_copies() {
+    let a = Piece::new(Colour::R, Shape::A);
+    let b = Piece::new(Colour::B, Shape::C);
+    assert!(holding_hash(0, &[a, b, a]) == holding_hash(0, &[a, a, b]));
+    assert!(holding_hash(0, &[a, a]) != 0);
+    assert!(holding_hash(0, &[a, a]) != holding_hash(0, &[a]));
+    assert!(holding_hash(0, &[a]) != holding_hash(1, &[a]));
+    assert!(square_key((0, 1), a) != square_key((1, 0), a));
+    assert!(square_key((-1, 0), a) != square_key((1, 0), a));
+}
@@ -0,0 +1,67 @@
+//! Move generation shouldn't touch the heap once it is under way.  This lives in its own test
+//! binary because it installs a counting global allocator.
+
+extern crate qwirkler;
+
+use qwirkler::{GameState, Greedy, Rules, Strategy, visit_partials};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+struct CountingAllocator;
+
+thread_local!(static ALLOCATIONS: Cell<usize> = Cell::new(0));
+
+fn allocations() -> usize {
+    return ALLOCATIONS.with(|count| count.get());
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        return System.alloc(layout);
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        return System.realloc(ptr, layout, new_size);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[test]
+fn visit_partials_should_not_allocate_per_candidate() {
+    let mut game_state = GameState::with_rules(2, Rules::standard(), 3);
+    let mut partials = 0;
+    while !game_state.is_over() {
+        let before = allocations();
+        let mut first_visit = None;
+        let mut last_visit = 0;
+        let stats = visit_partials(&game_state.board,
+                                   &game_state.players[game_state.turn].bag,
+                                   |_| {
+                                       let now = allocations();
+                                       first_visit = first_visit.or(Some(now));
+                                       last_visit = now;
+                                   });
+        let after = allocations();
+        partials += stats.partials;
+
+        // setting up (the start squares and the distinct pieces in the hand) may allocate,
+        // but nothing between the first candidate and the last one
+        if let Some(first_visit) = first_visit {
+            assert!(last_visit == first_visit);
+            assert!(after == last_visit);
+        }
+        assert!(after - before <= 16, "{} allocations for one position", after - before);
+
+        let chosen_move = Greedy.choose_move(&game_state);
+        game_state.apply_move(&chosen_move);
+    }
+    assert!(partials > 1000);
+}
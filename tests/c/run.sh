@@ -0,0 +1,8 @@
+#!/bin/sh
+# Builds the cdylib, then compiles and runs the C test program against it.
+set -e
+cd "$(dirname "$0")/../.."
+cargo build --release
+target=${CARGO_TARGET_DIR:-target}/release
+cc -Wall -Wextra -pedantic -Werror -std=c99 -Iinclude tests/c/test_ffi.c -L"$target" -lqwirkler -o "$target/test_ffi"
+LD_LIBRARY_PATH="$target" "$target/test_ffi"
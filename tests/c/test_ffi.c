@@ -0,0 +1,80 @@
+/* Plays a game through the C API.  Build and run with tests/c/run.sh. */
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "qwirkler.h"
+
+/* Unlike assert, still runs (and checks) its argument when built with NDEBUG. */
+#define CHECK(condition) \
+    do { \
+        if (!(condition)) { \
+            fprintf(stderr, "%s:%d: failed: %s\n", __FILE__, __LINE__, #condition); \
+            exit(1); \
+        } \
+    } while (0)
+
+static void check_null_handles(void) {
+    CHECK(qwirkler_game_turn(NULL) == -1);
+    CHECK(qwirkler_game_is_over(NULL) == -1);
+    CHECK(qwirkler_game_state_json(NULL, 0) == NULL);
+    CHECK(qwirkler_game_legal_moves(NULL) == NULL);
+    CHECK(qwirkler_game_apply_move(NULL, "swap") == QWIRKLER_BAD_ARGUMENT);
+    CHECK(qwirkler_game_best_move(NULL, "greedy", 0) == NULL);
+    CHECK(qwirkler_game_last_error(NULL) == NULL);
+    qwirkler_game_free(NULL);
+    qwirkler_string_free(NULL);
+}
+
+int main(void) {
+    check_null_handles();
+
+    QwirklerGame *game = qwirkler_game_new(2, "standard", 42);
+    CHECK(game != NULL);
+    CHECK(qwirkler_game_new(2, "copies=0", 42) == NULL);
+    CHECK(qwirkler_game_state_json(game, 2) == NULL);
+
+    char *error = qwirkler_game_last_error(game);
+    CHECK(error != NULL && strcmp(error, "") == 0);
+    qwirkler_string_free(error);
+
+    char *position = qwirkler_game_position(game, 0);
+    CHECK(position != NULL);
+    printf("start: %s\n", position);
+    qwirkler_string_free(position);
+
+    char *moves = qwirkler_game_legal_moves(game);
+    CHECK(moves != NULL && moves[0] == '[');
+    CHECK(strstr(moves, "\"score\"") != NULL);
+    CHECK(strstr(moves, "\"breakdown\":[{\"kind\":\"main\"") != NULL);
+    qwirkler_string_free(moves);
+
+    CHECK(qwirkler_game_apply_move(game, NULL) == QWIRKLER_BAD_ARGUMENT);
+    CHECK(qwirkler_game_apply_move(game, "nonsense") == QWIRKLER_BAD_NOTATION);
+    CHECK(qwirkler_game_apply_move(game, "20,20 R 11") == QWIRKLER_ILLEGAL_MOVE);
+    error = qwirkler_game_last_error(game);
+    CHECK(error != NULL && strlen(error) > 0);
+    printf("rejected: %s\n", error);
+    qwirkler_string_free(error);
+    CHECK(qwirkler_game_best_move(game, "oracle", 0) == NULL);
+
+    int turns = 0;
+    while (!qwirkler_game_is_over(game)) {
+        const char *strategy = qwirkler_game_turn(game) == 0 ? "greedy" : "search";
+        char *best = qwirkler_game_best_move(game, strategy, 5);
+        CHECK(best != NULL);
+        CHECK(qwirkler_game_apply_move(game, best) == QWIRKLER_OK);
+        qwirkler_string_free(best);
+        turns++;
+    }
+    CHECK(qwirkler_game_is_over(game) == 1);
+    CHECK(qwirkler_game_best_move(game, "greedy", 0) == NULL);
+    CHECK(qwirkler_game_apply_move(game, "swap") == QWIRKLER_ILLEGAL_MOVE);
+
+    char *json = qwirkler_game_state_json(game, 0);
+    CHECK(json != NULL && strstr(json, "\"over\":true") != NULL);
+    printf("after %d turns: %s\n", turns, json);
+    qwirkler_string_free(json);
+    qwirkler_game_free(game);
+    return 0;
+}